@@ -1,7 +1,6 @@
 #![no_std]
 #![no_main]
 
-use core::arch::asm;
 use riscv::register::sstatus::{self,SPP};
 #[macro_use]
 extern crate apps_lib;
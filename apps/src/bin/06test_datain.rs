@@ -3,7 +3,6 @@
 
 use core::arch::asm;
 
-#[macro_use]
 extern crate apps_lib;
 extern crate core;
 use core::slice;
@@ -13,7 +12,9 @@ use apps_lib::*;
 /// 正确输出：
 /// Test write0 OK!
 
-const STACK_SIZE: usize = 0x1000;
+const PAGE_SIZE: usize = 0x1000;
+// USER_STACK_SIZE in kernel/src/config.rs, below the stack there is an unmapped guard page
+const STACK_SIZE: usize = 0x10000;
 
 unsafe fn r_sp() -> usize {
     let mut sp: usize;
@@ -23,7 +24,8 @@ unsafe fn r_sp() -> usize {
 
 unsafe fn stack_range() -> (usize, usize) {
     let sp = r_sp();
-    let top = (sp + STACK_SIZE - 1) & (!(STACK_SIZE - 1));
+    // sp is still in the highest page of the user stack
+    let top = (sp + PAGE_SIZE - 1) & (!(PAGE_SIZE - 1));
     (top - STACK_SIZE, top)
 }
 
@@ -34,20 +36,20 @@ pub fn main() -> i32 {
             #[allow(clippy::zero_ptr)]
             slice::from_raw_parts(0x0 as *const _, 10)
         }),
-        -1
+        Err(Errno::EFAULT)
     );
     let (bottom, top) = unsafe { stack_range() };
     assert_eq!(
         write(STDOUT, unsafe {
             slice::from_raw_parts((top - 5) as *const _, 10)
         }),
-        -1
+        Err(Errno::EFAULT)
     );
     assert_eq!(
         write(STDOUT, unsafe {
             slice::from_raw_parts((bottom - 5) as *const _, 10)
         }),
-        -1
+        Err(Errno::EFAULT)
     );
     // TODO: test string located in .data section
     println!("Test write0 OK!");
@@ -0,0 +1,24 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+
+use apps_lib::yield_;
+
+const WIDTH: usize = 10;
+const HEIGHT: usize = 3;
+
+/// 与其它 write_* 程序交替输出，每打印一行就 yield 一次
+#[no_mangle]
+fn main() -> i32 {
+    for i in 0..HEIGHT {
+        for _ in 0..WIDTH {
+            print!("C");
+        }
+        println!(" [{}/{}]", i + 1, HEIGHT);
+        yield_();
+    }
+    println!("Test write_c OK!");
+    0
+}
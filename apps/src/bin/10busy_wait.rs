@@ -0,0 +1,18 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::get_time_us;
+
+const WAIT_US: usize = 1_000_000;
+
+/// 不主动 yield 的忙等程序，其它 app 应该在它等待期间被调度执行
+#[no_mangle]
+fn main() -> i32 {
+    let start = get_time_us();
+    println!("busy_wait: spinning for {}us without yield", WAIT_US);
+    while get_time_us() - start < WAIT_US {}
+    println!("Test busy_wait OK!");
+    0
+}
@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{get_time_us, set_priority, Errno};
+
+const PRIORITY: isize = 15;
+const RUN_US: usize = 2_000_000;
+
+/// 与其它 stride_p* 程序同时运行固定时长，count / priority 应大致相同
+#[no_mangle]
+fn main() -> i32 {
+    assert_eq!(set_priority(1), Err(Errno::EINVAL));
+    assert_eq!(set_priority(PRIORITY), Ok(PRIORITY as usize));
+    let start = get_time_us();
+    let mut count: usize = 0;
+    while get_time_us() - start < RUN_US {
+        count += 1;
+    }
+    println!(
+        "stride priority={} count={} count/priority={}",
+        PRIORITY,
+        count,
+        count / PRIORITY as usize
+    );
+    0
+}
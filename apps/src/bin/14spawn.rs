@@ -0,0 +1,29 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{spawn, yield_, Errno};
+
+const RETRY: usize = 100;
+
+/// 按名字重新运行 00hello_world，它结束之前 spawn 会返回 EBUSY
+#[no_mangle]
+fn main() -> i32 {
+    assert_eq!(spawn("no_such_app"), Err(Errno::ENOENT));
+    // 名字中不能有'\0'，也不能超过内核允许的长度
+    assert_eq!(spawn("00hello_world\0"), Err(Errno::EINVAL));
+    assert_eq!(spawn(core::str::from_utf8(&[b'a'; 65]).unwrap()), Err(Errno::EINVAL));
+    // 自己正在运行，不能被 spawn
+    assert_eq!(spawn("14spawn"), Err(Errno::EBUSY));
+    for _ in 0..RETRY {
+        if let Ok(id) = spawn("00hello_world") {
+            println!("spawn 00hello_world as app_{}", id);
+            println!("Test spawn OK!");
+            return 0;
+        }
+        yield_();
+    }
+    println!("spawn 00hello_world failed after {} retries", RETRY);
+    -1
+}
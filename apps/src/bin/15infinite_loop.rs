@@ -0,0 +1,13 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+
+/// 永远不会结束，内核应该在超过时间限制后 kill 它
+#[no_mangle]
+fn main() -> i32 {
+    println!("Into Test infinite_loop, kernel should kill this application after its time limit!");
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
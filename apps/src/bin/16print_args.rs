@@ -0,0 +1,15 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{argc, argv};
+
+/// 打印 apps/batch.toml 中配置的参数，以参数个数作为退出码
+#[no_mangle]
+fn main() -> i32 {
+    for i in 0..argc() {
+        println!("argv[{}] = {}", i, argv(i));
+    }
+    argc() as i32
+}
@@ -0,0 +1,15 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+
+#[no_mangle]
+fn main() -> i32{
+    println!("Into Test load_fault, we will insert an invalid load operation...");
+    println!("Kernel should kill this application!");
+    unsafe{
+        core::ptr::null::<u8>().read_volatile();
+    }
+    0
+}
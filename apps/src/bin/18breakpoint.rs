@@ -0,0 +1,19 @@
+#![no_std]
+#![no_main]
+
+use core::arch::asm;
+
+#[macro_use]
+extern crate apps_lib;
+
+#[no_mangle]
+fn main() -> i32{
+    println!("Try to execute ebreak in U Mode");
+    println!("Kernel should kill this application!");
+    unsafe{
+        asm!(
+            "ebreak"
+        )
+    }
+    0
+}
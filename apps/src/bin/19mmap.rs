@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{mmap, munmap, Errno};
+
+const START: usize = 0x1000_0000;
+const PAGE_SIZE: usize = 0x1000;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+
+/// 正确输出：
+/// Test mmap OK!
+#[no_mangle]
+fn main() -> i32 {
+    // 映射4页可读写的内存，写入后读回
+    let len = 4 * PAGE_SIZE;
+    assert_eq!(mmap(START, len, PROT_R | PROT_W), Ok(0));
+    for addr in (START..START + len).step_by(8) {
+        unsafe { (addr as *mut usize).write_volatile(addr) };
+    }
+    for addr in (START..START + len).step_by(8) {
+        assert_eq!(unsafe { (addr as *const usize).read_volatile() }, addr);
+    }
+
+    // 错误情况
+    assert_eq!(mmap(START + 8 * PAGE_SIZE + 1, PAGE_SIZE, PROT_R), Err(Errno::EINVAL)); // start 没有按页对齐
+    assert_eq!(mmap(START + 8 * PAGE_SIZE, 0, PROT_R), Err(Errno::EINVAL)); // 长度为0
+    assert_eq!(mmap(START + 8 * PAGE_SIZE, PAGE_SIZE, 0), Err(Errno::EINVAL)); // 没有任何权限
+    assert_eq!(mmap(START + 8 * PAGE_SIZE, PAGE_SIZE, 0x8), Err(Errno::EINVAL)); // 未定义的权限位
+    assert_eq!(mmap(START + 3 * PAGE_SIZE, 2 * PAGE_SIZE, PROT_R), Err(Errno::EEXIST)); // 与已有映射重叠
+    assert_eq!(mmap(START - PAGE_SIZE, 2 * PAGE_SIZE, PROT_R), Err(Errno::EEXIST));
+    assert_eq!(munmap(START + 8 * PAGE_SIZE, PAGE_SIZE), Err(Errno::EINVAL)); // 没有映射
+    assert_eq!(munmap(START + 3 * PAGE_SIZE, 2 * PAGE_SIZE), Err(Errno::EINVAL)); // 部分没有映射
+    assert_eq!(munmap(START + 1, PAGE_SIZE), Err(Errno::EINVAL)); // 没有按页对齐
+
+    // 取消中间两页的映射后，两端的页仍然可用，中间可以重新映射
+    assert_eq!(munmap(START + PAGE_SIZE, 2 * PAGE_SIZE), Ok(0));
+    assert_eq!(unsafe { (START as *const usize).read_volatile() }, START);
+    let last = START + 3 * PAGE_SIZE;
+    assert_eq!(unsafe { (last as *const usize).read_volatile() }, last);
+    assert_eq!(munmap(START + PAGE_SIZE, PAGE_SIZE), Err(Errno::EINVAL));
+    assert_eq!(mmap(START + PAGE_SIZE, 2 * PAGE_SIZE, PROT_R | PROT_W), Ok(0));
+    // 新映射的内存是清零的
+    assert_eq!(unsafe { ((START + PAGE_SIZE) as *const usize).read_volatile() }, 0);
+    assert_eq!(munmap(START, len), Ok(0));
+    println!("Test mmap OK!");
+    0
+}
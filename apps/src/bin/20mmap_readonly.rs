@@ -0,0 +1,20 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::mmap;
+
+const START: usize = 0x1000_0000;
+const PAGE_SIZE: usize = 0x1000;
+const PROT_R: usize = 1;
+
+#[no_mangle]
+fn main() -> i32 {
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_R), Ok(0));
+    assert_eq!(unsafe { (START as *const usize).read_volatile() }, 0);
+    println!("Write to a read-only mapping...");
+    println!("Kernel should kill this application!");
+    unsafe { (START as *mut usize).write_volatile(1) };
+    0
+}
@@ -0,0 +1,22 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{mmap, munmap};
+
+const START: usize = 0x1000_0000;
+const PAGE_SIZE: usize = 0x1000;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+
+#[no_mangle]
+fn main() -> i32 {
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_R | PROT_W), Ok(0));
+    unsafe { (START as *mut usize).write_volatile(1) };
+    assert_eq!(munmap(START, PAGE_SIZE), Ok(0));
+    println!("Read an unmapped page...");
+    println!("Kernel should kill this application!");
+    unsafe { (START as *const usize).read_volatile() };
+    0
+}
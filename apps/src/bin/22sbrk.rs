@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{sbrk, Errno};
+
+const PAGE_SIZE: usize = 0x1000;
+
+/// 正确输出：
+/// Test sbrk OK!
+#[no_mangle]
+fn main() -> i32 {
+    // 堆初始为空，堆底按页对齐
+    let bottom = sbrk(0).unwrap();
+    assert!(bottom > 0);
+    assert_eq!(bottom % PAGE_SIZE, 0);
+    // 不能缩小到堆底以下
+    assert_eq!(sbrk(-1), Err(Errno::ENOMEM));
+    assert_eq!(sbrk(0), Ok(bottom));
+
+    // 扩展两页半，新的内存可读写且已清零
+    let len = 2 * PAGE_SIZE + PAGE_SIZE / 2;
+    assert_eq!(sbrk(len as isize), Ok(bottom));
+    assert_eq!(sbrk(0), Ok(bottom + len));
+    let heap = bottom;
+    for addr in (heap..heap + len).step_by(8) {
+        assert_eq!(unsafe { (addr as *const usize).read_volatile() }, 0);
+        unsafe { (addr as *mut usize).write_volatile(addr) };
+    }
+    for addr in (heap..heap + len).step_by(8) {
+        assert_eq!(unsafe { (addr as *const usize).read_volatile() }, addr);
+    }
+
+    // 缩小一页，剩下的内容不变
+    assert_eq!(sbrk(-(PAGE_SIZE as isize)), Ok(bottom + len));
+    let len = len - PAGE_SIZE;
+    assert_eq!(sbrk(0), Ok(bottom + len));
+    for addr in (heap..heap + len).step_by(8) {
+        assert_eq!(unsafe { (addr as *const usize).read_volatile() }, addr);
+    }
+    assert_eq!(sbrk(-(len as isize) - 1), Err(Errno::ENOMEM));
+    assert_eq!(sbrk(-(len as isize)), Ok(bottom + len));
+    assert_eq!(sbrk(0), Ok(bottom));
+    // 不能扩展到用户地址空间之外
+    assert_eq!(sbrk(isize::MAX), Err(Errno::ENOMEM));
+    assert_eq!(sbrk(0), Ok(bottom));
+    println!("Test sbrk OK!");
+    0
+}
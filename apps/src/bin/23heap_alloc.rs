@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::collections::BTreeMap;
+use core::fmt::Write;
+
+/// 正确输出：
+/// Test heap alloc OK!
+#[no_mangle]
+fn main() -> i32 {
+    // 超过一次扩展的大小，堆需要多次增长
+    let mut v: Vec<usize> = Vec::new();
+    for i in 0..10000 {
+        v.push(i);
+    }
+    assert!(v.iter().enumerate().all(|(i, x)| i == *x));
+    assert_eq!(v.iter().sum::<usize>(), 10000 * 9999 / 2);
+    drop(v);
+
+    let mut s = String::new();
+    for i in 0..100 {
+        write!(s, "{},", i).unwrap();
+    }
+    assert!(s.starts_with("0,1,2,"));
+    assert!(s.ends_with("98,99,"));
+
+    let mut map = BTreeMap::new();
+    for i in 0..500usize {
+        map.insert(i, i * i);
+    }
+    assert_eq!(map[&300], 90000);
+    println!("Test heap alloc OK!");
+    0
+}
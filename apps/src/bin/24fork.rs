@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+extern crate alloc;
+use alloc::vec::Vec;
+use apps_lib::{exit, fork, yield_};
+
+const N: usize = 16 * 1024;
+const CHILDREN: usize = 4;
+
+// 32 KiB in .bss, shared copy-on-write after fork
+static mut DATA: [usize; N] = [0; N];
+
+fn check(expected: impl Fn(usize) -> usize) {
+    for (i, x) in unsafe { DATA.iter().enumerate() } {
+        assert_eq!(*x, expected(i));
+    }
+}
+
+fn fill(value: impl Fn(usize) -> usize) {
+    for (i, x) in unsafe { DATA.iter_mut().enumerate() } {
+        *x = value(i);
+    }
+}
+
+/// 正确输出：
+/// Test fork OK!
+#[no_mangle]
+fn main() -> i32 {
+    fill(|i| i);
+    let heap: Vec<usize> = (0..1000).collect();
+    for k in 1..=CHILDREN {
+        let pid = fork().unwrap();
+        if pid == 0 {
+            // 子任务看到的是fork时父任务的内存，写入不影响父任务和其他子任务
+            check(|i| i);
+            assert!(heap.iter().enumerate().all(|(i, x)| i == *x));
+            fill(|i| i * k);
+            yield_();
+            check(|i| i * k);
+            println!("fork child {} OK", k);
+            exit(0);
+        }
+    }
+    // 父任务在子任务运行期间写入，子任务的写入对它不可见
+    for _ in 0..CHILDREN {
+        yield_();
+    }
+    check(|i| i);
+    fill(|i| i + 1);
+    for _ in 0..CHILDREN {
+        yield_();
+    }
+    check(|i| i + 1);
+    assert!(heap.iter().enumerate().all(|(i, x)| i == *x));
+    println!("Test fork OK!");
+    0
+}
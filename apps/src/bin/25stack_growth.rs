@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+
+// 每层递归使用约 1 KiB 的栈
+fn recurse(depth: usize) -> usize {
+    let mut buf = [0u8; 1024];
+    buf[depth % 1024] = depth as u8;
+    let buf = core::hint::black_box(buf);
+    if depth == 0 {
+        buf[0] as usize
+    } else {
+        recurse(depth - 1) + buf[depth % 1024] as usize
+    }
+}
+
+/// 正确输出：
+/// Test stack growth OK!
+#[no_mangle]
+fn main() -> i32 {
+    // 用到用户栈的大部分（约 40 KiB），其中的页在缺页时按需分配
+    let expected: usize = (0..=40usize).map(|d| d as u8 as usize).sum();
+    assert_eq!(recurse(40), expected);
+    println!("Test stack growth OK!");
+    0
+}
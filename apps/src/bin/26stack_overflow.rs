@@ -0,0 +1,18 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+
+#[allow(unconditional_recursion)]
+fn recurse(depth: usize) -> usize {
+    let buf = core::hint::black_box([depth as u8; 512]);
+    recurse(depth + 1) + buf[0] as usize
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("Recurse until the user stack overflows...");
+    println!("Kernel should kill this application!");
+    recurse(0) as i32
+}
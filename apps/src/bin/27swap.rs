@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{mmap, munmap};
+
+const START: usize = 0x2000_0000;
+const PAGE_SIZE: usize = 0x1000;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+// 超过内核中每个地址空间驻留页的上限，一部分页会被换出到交换区
+const PAGES: usize = 1024;
+
+fn page_value(page: usize, round: usize) -> usize {
+    page * 0x9e37_79b9 + round
+}
+
+/// 正确输出：
+/// Test swap OK!
+#[no_mangle]
+fn main() -> i32 {
+    let len = PAGES * PAGE_SIZE;
+    assert_eq!(mmap(START, len, PROT_R | PROT_W), Ok(0));
+    let word = |page: usize, offset: usize| (START + page * PAGE_SIZE + offset) as *mut usize;
+    // 每页的开头和结尾各写一个值
+    for page in 0..PAGES {
+        unsafe {
+            word(page, 0).write_volatile(page_value(page, 0));
+            word(page, PAGE_SIZE - 8).write_volatile(!page_value(page, 0));
+        }
+    }
+    // 倒序读回并改写，之前换出的页需要换入
+    for page in (0..PAGES).rev() {
+        unsafe {
+            assert_eq!(word(page, 0).read_volatile(), page_value(page, 0));
+            assert_eq!(word(page, PAGE_SIZE - 8).read_volatile(), !page_value(page, 0));
+            word(page, 0).write_volatile(page_value(page, 1));
+        }
+    }
+    // 跳跃访问
+    for i in 0..PAGES {
+        let page = i * 7 % PAGES;
+        assert_eq!(unsafe { word(page, 0).read_volatile() }, page_value(page, 1));
+    }
+    assert_eq!(munmap(START, len), Ok(0));
+    println!("Test swap OK!");
+    0
+}
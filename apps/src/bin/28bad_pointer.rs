@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::errno::decode;
+use apps_lib::syscall::{syscall6, TimeVal};
+use apps_lib::{get_time, mmap, Errno, SysResult};
+
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_SPAWN: usize = 400;
+
+const START: usize = 0x1000_0000;
+const PAGE_SIZE: usize = 0x1000;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+const KERNEL_BASE: usize = 0x8020_0000;
+
+fn write_raw(addr: usize, len: usize) -> SysResult {
+    decode(syscall6(SYSCALL_WRITE, [1, addr, len, 0, 0, 0]))
+}
+
+fn spawn_raw(addr: usize) -> SysResult {
+    decode(syscall6(SYSCALL_SPAWN, [addr, 0, 0, 0, 0, 0]))
+}
+
+/// 正确输出：
+/// Test bad pointer OK!
+#[no_mangle]
+fn main() -> i32 {
+    // 没有映射、属于内核或者超出用户地址空间的缓冲区
+    assert_eq!(write_raw(START, 8), Err(Errno::EFAULT));
+    assert_eq!(write_raw(KERNEL_BASE, 8), Err(Errno::EFAULT));
+    assert_eq!(write_raw(usize::MAX - 4, 8), Err(Errno::EFAULT));
+    let msg = b"ok\n";
+    assert_eq!(write_raw(msg.as_ptr() as usize, usize::MAX), Err(Errno::EFAULT));
+    assert_eq!(get_time(core::ptr::null_mut(), 0), Err(Errno::EFAULT));
+    assert_eq!(get_time(KERNEL_BASE as *mut TimeVal, 0), Err(Errno::EFAULT));
+
+    // 只读的页不能由内核代为写入，可写的页在第一次访问前也可以写入
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_R), Ok(0));
+    assert_eq!(get_time(START as *mut TimeVal, 0), Err(Errno::EFAULT));
+    let rw = START + 2 * PAGE_SIZE;
+    assert_eq!(mmap(rw, 2 * PAGE_SIZE, PROT_R | PROT_W), Ok(0));
+    let ts = (rw + PAGE_SIZE - 8) as *mut TimeVal;
+    assert_eq!(get_time(ts, 0), Ok(0));
+    assert!(unsafe { ts.read_unaligned() }.sec < 1000);
+    assert_eq!(write_raw(START, 0), Ok(0));
+
+    // 字符串没有映射或者过长
+    assert_eq!(spawn_raw(START + PAGE_SIZE), Err(Errno::EFAULT));
+    assert_eq!(spawn_raw(KERNEL_BASE), Err(Errno::EFAULT));
+    let long_name = [b'a'; 100];
+    assert_eq!(spawn_raw(long_name.as_ptr() as usize), Err(Errno::EINVAL));
+    println!("Test bad pointer OK!");
+    0
+}
@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::write;
+
+const FD_STDOUT: usize = 1;
+
+/// 正确输出（之前是若干不可打印的字节）：
+/// Test binary write OK!
+#[no_mangle]
+fn main() -> i32 {
+    // 所有字节值，其中有很多不是合法的UTF-8
+    let mut bytes = [0u8; 256];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    assert_eq!(write(FD_STDOUT, &bytes), Ok(256));
+    // 不完整的多字节字符、单独的后续字节和超长编码
+    let invalid: [&[u8]; 4] = [b"\xe4\xb8", b"\x80\x80", b"\xc0\xaf", b"\xf4\x90\x80\x80"];
+    for data in invalid {
+        assert_eq!(write(FD_STDOUT, data), Ok(data.len()));
+    }
+    println!("\nTest binary write OK!");
+    0
+}
@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::errno::decode;
+use apps_lib::syscall::syscall6;
+use apps_lib::Errno;
+
+const UNKNOWN_SYSCALLS: [usize; 3] = [0, 1000, usize::MAX];
+
+/// 正确输出（内核只报告一次未知的系统调用）：
+/// Test unknown syscall OK!
+#[no_mangle]
+fn main() -> i32 {
+    for id in UNKNOWN_SYSCALLS {
+        assert_eq!(decode(syscall6(id, [0; 6])), Err(Errno::ENOSYS));
+    }
+    println!("Test unknown syscall OK!");
+    0
+}
@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+use core::arch::asm;
+use core::ptr::addr_of_mut;
+
+#[macro_use]
+extern crate apps_lib;
+
+static mut WORDS: [u32; 2] = [0; 2];
+
+#[no_mangle]
+fn main() -> i32{
+    println!("Try to do a misaligned atomic store in U Mode");
+    println!("Kernel should kill this application!");
+    // 普通的非对齐读写可能由硬件或SBI完成，AMO的地址必须自然对齐
+    unsafe{
+        let addr = addr_of_mut!(WORDS) as usize + 1;
+        asm!(
+            "amoswap.w zero, zero, ({})",
+            in(reg) addr
+        )
+    }
+    0
+}
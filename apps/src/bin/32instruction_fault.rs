@@ -0,0 +1,24 @@
+#![no_std]
+#![no_main]
+
+use core::arch::asm;
+
+#[macro_use]
+extern crate apps_lib;
+
+// .rodata 所在的页只读、不可执行
+static NOT_CODE: [u32; 4] = [0; 4];
+
+#[no_mangle]
+fn main() -> i32{
+    println!("Try to execute a non-executable page in U Mode");
+    println!("Kernel should kill this application!");
+    // 用户态无法访问到会产生 InstructionFault（PMP）的物理地址，取指失败表现为 InstructionPageFault
+    unsafe{
+        asm!(
+            "jalr {}",
+            in(reg) NOT_CODE.as_ptr()
+        )
+    }
+    0
+}
@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate apps_lib;
+use apps_lib::{mmap, munmap, sbrk, Errno};
+
+const PAGE_SIZE: usize = 0x1000;
+const USER_STACK_SIZE: usize = 16 * PAGE_SIZE;
+const USER_HEAP_SIZE: usize = 0x100_0000;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+
+/// 正确输出：
+/// Test mmap reserved OK!
+#[no_mangle]
+fn main() -> i32 {
+    // 地址空间布局：guard page、用户栈、guard page、堆，堆最多增长 USER_HEAP_SIZE
+    let heap_bottom = sbrk(0).unwrap();
+    let user_stack_top = heap_bottom - PAGE_SIZE;
+    let user_stack_bottom = user_stack_top - USER_STACK_SIZE;
+    let heap_limit = heap_bottom + USER_HEAP_SIZE;
+
+    // 第0页
+    assert_eq!(mmap(0, PAGE_SIZE, PROT_R), Err(Errno::EINVAL));
+    // 用户栈之下的guard page
+    assert_eq!(mmap(user_stack_bottom - PAGE_SIZE, PAGE_SIZE, PROT_R | PROT_W), Err(Errno::EINVAL));
+    assert_eq!(mmap(user_stack_bottom - 2 * PAGE_SIZE, 2 * PAGE_SIZE, PROT_R), Err(Errno::EINVAL));
+    // 用户栈之上的guard page
+    assert_eq!(mmap(user_stack_top, PAGE_SIZE, PROT_R), Err(Errno::EINVAL));
+    // 堆可以增长到的区域，即使堆还没有增长到那里
+    assert_eq!(mmap(heap_bottom, PAGE_SIZE, PROT_R | PROT_W), Err(Errno::EINVAL));
+    assert_eq!(mmap(heap_limit - PAGE_SIZE, 2 * PAGE_SIZE, PROT_R), Err(Errno::EINVAL));
+    // 紧挨着这个区域之上可以映射
+    assert_eq!(mmap(heap_limit, PAGE_SIZE, PROT_R | PROT_W), Ok(0));
+
+    // 堆不能增长到 USER_HEAP_SIZE 以上
+    assert_eq!(sbrk((USER_HEAP_SIZE + 1) as isize), Err(Errno::ENOMEM));
+    assert_eq!(sbrk(0), Ok(heap_bottom));
+
+    // 只有 mmap 建立的映射可以 munmap：堆、用户栈和程序本身都不行
+    assert_eq!(sbrk(2 * PAGE_SIZE as isize), Ok(heap_bottom));
+    assert_eq!(munmap(heap_bottom, PAGE_SIZE), Err(Errno::EINVAL));
+    assert_eq!(munmap(user_stack_top - PAGE_SIZE, PAGE_SIZE), Err(Errno::EINVAL));
+    assert_eq!(munmap(main as usize & !(PAGE_SIZE - 1), PAGE_SIZE), Err(Errno::EINVAL));
+    // 超出 mmap 的映射
+    assert_eq!(munmap(heap_limit, 2 * PAGE_SIZE), Err(Errno::EINVAL));
+    // 堆仍然可以增长和缩小
+    assert_eq!(sbrk(PAGE_SIZE as isize), Ok(heap_bottom + 2 * PAGE_SIZE));
+    unsafe { ((heap_bottom + 2 * PAGE_SIZE) as *mut usize).write_volatile(1) };
+    assert_eq!(sbrk(-3 * PAGE_SIZE as isize), Ok(heap_bottom + 3 * PAGE_SIZE));
+    assert_eq!(sbrk(0), Ok(heap_bottom));
+    assert_eq!(munmap(heap_limit, PAGE_SIZE), Ok(0));
+    println!("Test mmap reserved OK!");
+    0
+}
@@ -8,7 +8,7 @@ pub const STDOUT:usize = 1;
 
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        write(STDOUT, s.as_bytes());
+        write(STDOUT, s.as_bytes()).map_err(|_| fmt::Error)?;
         Ok(())
     }
 }
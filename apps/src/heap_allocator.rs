@@ -0,0 +1,47 @@
+// the user heap lives in [heap_bottom, brk) and grows through sys_sbrk when it runs out of space
+
+use crate::sbrk;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::{self, NonNull};
+use linked_list_allocator::LockedHeap;
+
+const PAGE_SIZE: usize = 0x1000;
+// grow the heap by at least this many bytes at a time
+const HEAP_GROW_SIZE: usize = 4 * PAGE_SIZE;
+
+struct SbrkHeap(LockedHeap);
+
+#[global_allocator]
+static HEAP: SbrkHeap = SbrkHeap(LockedHeap::empty());
+
+unsafe impl GlobalAlloc for SbrkHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut heap = self.0.lock();
+        if let Ok(ptr) = heap.allocate_first_fit(layout) {
+            return ptr.as_ptr();
+        }
+        // the new memory is contiguous with the old heap, so one grow is always enough
+        let grow = (layout.size() + layout.align()).max(HEAP_GROW_SIZE);
+        let grow = (grow + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+        let old_brk = match sbrk(grow as isize) {
+            Ok(old_brk) => old_brk,
+            Err(_) => return ptr::null_mut(),
+        };
+        if heap.size() == 0 {
+            heap.init(old_brk as *mut u8, grow);
+        } else {
+            heap.extend(grow);
+        }
+        heap.allocate_first_fit(layout)
+            .map_or(ptr::null_mut(), |ptr| ptr.as_ptr())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.lock().deallocate(NonNull::new_unchecked(ptr), layout);
+    }
+}
+
+#[alloc_error_handler]
+fn handle_alloc_error(layout: Layout) -> ! {
+    panic!("Heap allocation error, layout = {:?}", layout);
+}
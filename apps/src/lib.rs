@@ -1,10 +1,17 @@
 #![no_std]
 #![feature(panic_info_message)]
 #![feature(linkage)]
+#![feature(alloc_error_handler)]
+
+extern crate alloc;
 
 #[macro_use]
 pub mod console;
 pub mod syscall;
+// the same errno table as the kernel
+#[path = "../../kernel/src/syscall/errno.rs"]
+pub mod errno;
+mod heap_allocator;
 mod lang_items;
 
 
@@ -14,10 +21,18 @@ fn main() -> i32 {
     panic!("Cannot find main!");
 }
 
+// set by _start, the kernel puts the strings and the argv array on the user stack
+static mut ARGC: usize = 0;
+static mut ARGV: usize = 0;
+
 #[no_mangle]
 #[link_section=".text.entry"]
-pub extern "C" fn _start() -> !{
+pub extern "C" fn _start(argc: usize, argv: usize) -> !{
     clear_bss();
+    unsafe{
+        ARGC = argc;
+        ARGV = argv;
+    }
     exit(main());
     panic!("Unreachabel after sys_exit in apps !");
 }
@@ -32,14 +47,81 @@ fn clear_bss() {
     });
 }
 
+pub fn argc() -> usize{
+    unsafe{ ARGC }
+}
+
+// the i-th argument from apps/batch.toml
+pub fn argv(i: usize) -> &'static str{
+    assert!(i < argc(), "argv index out of range");
+    unsafe{
+        let ptr = ((ARGV + i * core::mem::size_of::<usize>()) as *const usize).read_volatile() as *const u8;
+        let len = (0usize..).find(|j| ptr.add(*j).read_volatile() == 0).unwrap();
+        core::str::from_utf8(core::slice::from_raw_parts(ptr, len)).unwrap()
+    }
+}
+
 use syscall::*;
-pub fn write(fd: usize,buf: &[u8]) -> isize{
-    sys_write(fd,buf)
+pub use errno::{Errno, SysResult};
+use errno::decode;
+
+// the wrappers below return the syscall result or the Errno the kernel reported
+pub fn write(fd: usize,buf: &[u8]) -> SysResult{
+    decode(sys_write(fd,buf))
 }
 pub fn exit(exit_code: i32) -> isize{
     sys_exit(exit_code)
 }
 
-pub fn get_time(ts: *mut TimeVal,_tz: usize) -> isize{
-    sys_get_time(ts,_tz) as isize
+pub fn yield_() -> isize{
+    sys_yield()
+}
+
+pub fn set_priority(prio: isize) -> SysResult{
+    decode(sys_set_priority(prio))
+}
+
+// the kernel reads the name up to '\0' and accepts at most this many bytes
+const MAX_APP_NAME_LEN: usize = 64;
+
+// starts the app with this name again, returns its app id; the app must have exited,
+// a running app gets EBUSY, and an unknown name gets ENOENT
+pub fn spawn(name: &str) -> SysResult{
+    if name.len() > MAX_APP_NAME_LEN || name.as_bytes().contains(&0) {
+        return Err(Errno::EINVAL);
+    }
+    let mut buf = [0u8; MAX_APP_NAME_LEN + 1];
+    buf[..name.len()].copy_from_slice(name.as_bytes());
+    decode(sys_spawn(buf.as_ptr()))
+}
+
+// returns the child's task id in the parent and 0 in the child; the child's memory is a
+// copy-on-write copy of the parent's, and it shows up in the report through its app
+pub fn fork() -> SysResult{
+    decode(sys_fork())
+}
+
+// prot: bit0 = R, bit1 = W, bit2 = X; start must be page aligned, returns 0
+pub fn mmap(start: usize, len: usize, prot: usize) -> SysResult{
+    decode(sys_mmap(start, len, prot))
+}
+
+pub fn munmap(start: usize, len: usize) -> SysResult{
+    decode(sys_munmap(start, len))
+}
+
+// moves the end of the heap by size bytes, returns the old end;
+// the global allocator uses it too, so don't mix the two in one app
+pub fn sbrk(size: isize) -> SysResult{
+    decode(sys_sbrk(size))
+}
+
+pub fn get_time(ts: *mut TimeVal,_tz: usize) -> SysResult{
+    decode(sys_get_time(ts,_tz))
+}
+
+pub fn get_time_us() -> usize{
+    let mut ts = TimeVal{sec: 0, usec: 0};
+    sys_get_time(&mut ts, 0);
+    ts.sec * 1_000_000 + ts.usec
 }
\ No newline at end of file
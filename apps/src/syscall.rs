@@ -15,6 +15,7 @@ pub const SYSCALL_FORK: usize = 220;
 pub const SYSCALL_EXEC: usize = 221;
 pub const SYSCALL_WAITPID: usize = 260;
 pub const SYSCALL_SET_PRIORITY: usize = 140;
+pub const SYSCALL_SBRK: usize = 214;
 pub const SYSCALL_MUNMAP: usize = 215;
 pub const SYSCALL_MMAP: usize = 222;
 pub const SYSCALL_SPAWN: usize = 400;
@@ -54,8 +55,36 @@ pub fn sys_exit(exit_code: i32) -> isize {
     syscall(SYSCALL_EXIT, [exit_code as usize, 0, 0])
 }
 
+pub fn sys_yield() -> isize {
+    syscall(SYSCALL_YIELD, [0, 0, 0])
+}
+
+pub fn sys_set_priority(prio: isize) -> isize {
+    syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
+}
+
 pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize{
-    syscall(SYSCALL_GET_TIME,[ts as usize,_tz as usize,0])
+    syscall(SYSCALL_GET_TIME,[ts as usize,_tz,0])
+}
+
+pub fn sys_spawn(name: *const u8) -> isize {
+    syscall(SYSCALL_SPAWN, [name as usize, 0, 0])
+}
+
+pub fn sys_sbrk(size: isize) -> isize {
+    syscall(SYSCALL_SBRK, [size as usize, 0, 0])
+}
+
+pub fn sys_fork() -> isize {
+    syscall(SYSCALL_FORK, [0, 0, 0])
+}
+
+pub fn sys_mmap(start: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MMAP, [start, len, prot])
+}
+
+pub fn sys_munmap(start: usize, len: usize) -> isize {
+    syscall(SYSCALL_MUNMAP, [start, len, 0])
 }
 
 pub fn syscall6(id: usize, args: [usize; 6]) -> isize {
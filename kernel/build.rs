@@ -1,18 +1,215 @@
-use std::fs::{read_dir,File};
+use std::fs::{read_dir,read_to_string,File};
 use std::io::{Result,Write};
 
 static TARGET_PATH: &str = "../apps/target/riscv64gc-unknown-none-elf/release/";
+static MANIFEST_PATH: &str = "../apps/batch.toml";
 fn main(){
     println!("cargo:rerun-if-changed=../apps/src/");
     println!("cargo:rerun-if-changed={}", TARGET_PATH);
+    println!("cargo:rerun-if-changed={}", MANIFEST_PATH);
+    // read by the trace feature through option_env!
+    println!("cargo:rerun-if-env-changed=TRACE_APP");
     insert_app_data().unwrap();
 }
 
+// per-app settings from the optional manifest, 0 means kernel default
+#[derive(Default)]
+struct AppConfig{
+    name: String,
+    args: Vec<String>,
+    time_limit_ms: usize,
+    expected_exit_code: i64,
+    // expect the kernel to kill the app with this reason instead of exiting
+    expected_kill: Option<String>,
+}
+
+impl AppConfig{
+    fn new(name: &str) -> Self{
+        Self{
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+// a value in batch.toml
+enum Value{
+    Str(String),
+    Int(i64),
+    List(Vec<String>),
+}
+
+// parse the small subset of toml used by batch.toml:
+// [[app]] tables with `key = "string"`, `key = integer` or `key = ["string", ...]` entries on one line,
+// basic strings support the \" \\ \n \t \r escapes, `#` starts a comment outside of strings
+fn parse_manifest() -> Vec<AppConfig>{
+    let content = match read_to_string(MANIFEST_PATH) {
+        Ok(content) => content,
+        Err(_) => return Vec::new(),
+    };
+    let mut configs: Vec<AppConfig> = Vec::new();
+    for (lineno, line) in content.lines().enumerate(){
+        let err = |msg: &str| -> ! { panic!("{}:{}: {}", MANIFEST_PATH, lineno + 1, msg) };
+        let mut parser = Parser::new(line);
+        if parser.at_end() {
+            continue;
+        }
+        if parser.eat_str("[[app]]") {
+            parser.expect_end().unwrap_or_else(|msg| err(&msg));
+            configs.push(AppConfig::default());
+            continue;
+        }
+        let key = parser.key();
+        if key.is_empty() || !parser.eat_str("=") {
+            err("expect `key = value`");
+        }
+        let value = parser.value().unwrap_or_else(|msg| err(&msg));
+        parser.expect_end().unwrap_or_else(|msg| err(&msg));
+        let config = configs.last_mut().unwrap_or_else(|| err("key outside of [[app]]"));
+        match (key.as_str(), value) {
+            ("name", Value::Str(name)) => config.name = name,
+            ("args", Value::List(args)) => config.args = args,
+            ("time_limit_ms", Value::Int(ms)) if ms >= 0 => config.time_limit_ms = ms as usize,
+            ("expected_exit_code", Value::Int(code)) => config.expected_exit_code = code,
+            ("expected_kill", Value::Str(reason)) => config.expected_kill = Some(reason),
+            ("name" | "args" | "time_limit_ms" | "expected_exit_code" | "expected_kill", _) => {
+                err(&format!("bad value for `{}`", key))
+            },
+            _ => err(&format!("unknown key `{}`", key)),
+        }
+        // arguments are passed to the app as '\0' terminated strings
+        if config.args.iter().any(|arg| arg.contains('\0')) {
+            err("arguments cannot contain '\\0'");
+        }
+    }
+    configs
+}
+
+// a cursor over one line of the manifest, skipping spaces before every token
+struct Parser{
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser{
+    fn new(line: &str) -> Self{
+        Self{
+            chars: line.chars().collect(),
+            pos: 0,
+        }
+    }
+    fn peek(&mut self) -> Option<char>{
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+    // only spaces or a comment are left
+    fn at_end(&mut self) -> bool{
+        matches!(self.peek(), None | Some('#'))
+    }
+    fn expect_end(&mut self) -> std::result::Result<(), String>{
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(format!("unexpected `{}`", self.chars[self.pos..].iter().collect::<String>()))
+        }
+    }
+    fn eat_str(&mut self, s: &str) -> bool{
+        self.peek();
+        let s: Vec<char> = s.chars().collect();
+        if self.chars[self.pos..].starts_with(&s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+    // consume the longest run of chars matching f
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String{
+        self.peek();
+        let start = self.pos;
+        while self.pos < self.chars.len() && f(self.chars[self.pos]) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+    // a bare key: letters, digits, `_` and `-`
+    fn key(&mut self) -> String{
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    }
+    fn value(&mut self) -> std::result::Result<Value, String>{
+        match self.peek() {
+            Some('"') => self.string().map(Value::Str),
+            Some('[') => {
+                self.pos += 1;
+                let mut list = Vec::new();
+                while self.peek() != Some(']') {
+                    list.push(self.string()?);
+                    if !self.eat_str(",") && self.peek() != Some(']') {
+                        return Err("expect `,` or `]` after a list element".to_string());
+                    }
+                }
+                self.pos += 1;
+                Ok(Value::List(list))
+            },
+            _ => {
+                let digits = self.take_while(|c| c == '-' || c.is_ascii_digit());
+                digits
+                    .parse()
+                    .map(Value::Int)
+                    .map_err(|_| "expect a string, a list of strings or an integer".to_string())
+            },
+        }
+    }
+    fn string(&mut self) -> std::result::Result<String, String>{
+        if self.peek() != Some('"') {
+            return Err("expect a string".to_string());
+        }
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = *self.chars.get(self.pos).ok_or("unterminated string")?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = self.chars.get(self.pos).copied();
+                    self.pos += 1;
+                    s.push(match escaped {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        _ => return Err("unsupported escape in string".to_string()),
+                    });
+                },
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+// quote s for the .string and .incbin directives of link_app.S
+fn asm_string(s: &str) -> String{
+    let mut quoted = String::from("\"");
+    for byte in s.bytes(){
+        match byte {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            0x20..=0x7e => quoted.push(byte as char),
+            _ => quoted.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 fn insert_app_data() -> Result<()>{
     let mut f = File::create("src/link_app.S").unwrap();
-    // app's name without .rs ext 
+    // app's name without .rs ext
     let mut apps:Vec<_> = read_dir("../apps/src/bin").unwrap()
-        .into_iter()
         .map(|dir_entry|{
             let mut name_withext = dir_entry.unwrap().file_name().into_string().unwrap();
             // drop ext str
@@ -22,6 +219,20 @@ fn insert_app_data() -> Result<()>{
         .collect();
     apps.sort();
 
+    // without a manifest every app runs in alphabetical order, otherwise only the listed ones in manifest order
+    let mut configs = parse_manifest();
+    if configs.is_empty() {
+        configs = apps.iter().map(|app| AppConfig::new(app)).collect();
+    }
+    for (i, config) in configs.iter().enumerate(){
+        if !apps.contains(&config.name) {
+            panic!("{}: unknown app `{}`", MANIFEST_PATH, config.name);
+        }
+        if configs[..i].iter().any(|c| c.name == config.name) {
+            panic!("{}: app `{}` listed twice", MANIFEST_PATH, config.name);
+        }
+    }
+
     // generate linker file
     writeln!(
         f,
@@ -30,20 +241,46 @@ fn insert_app_data() -> Result<()>{
     .global _num_app
 _num_app:
     .quad {}"#
-        ,apps.len()
+        ,configs.len()
     )?;
-    for i in 0..apps.len(){
+    for i in 0..configs.len(){
         writeln!(f, r#"    .quad app_{}_start"#, i)?;
     }
-    writeln!(f, r#"    .quad app_{}_end"#, apps.len() - 1)?;
+    writeln!(f, r#"    .quad app_{}_end"#, configs.len() - 1)?;
+
+    // one entry per app: time_limit_ms, expected_exit_code, expected_kill, argc, argv
+    writeln!(f, r#"    .global _app_manifest
+_app_manifest:"#)?;
+    for (idx,config) in configs.iter().enumerate(){
+        let expected_kill = match config.expected_kill {
+            Some(_) => format!("app_{}_expected_kill", idx),
+            None => "0".to_string(),
+        };
+        writeln!(
+            f,
+            r#"    .quad {}, {}, {}, {}, app_{}_args"#,
+            config.time_limit_ms, config.expected_exit_code, expected_kill, config.args.len(), idx
+        )?;
+    }
+    for (idx,config) in configs.iter().enumerate(){
+        writeln!(f, r#"app_{}_args:"#, idx)?;
+        for arg in config.args.iter(){
+            writeln!(f, r#"    .string {}"#, asm_string(arg))?;
+        }
+        if let Some(reason) = &config.expected_kill {
+            writeln!(f, r#"app_{}_expected_kill:
+    .string {}"#, idx, asm_string(reason))?;
+        }
+    }
+
     writeln!(f, r#"    .global _app_names
 _app_names:"#)?;
-    for i in 0..apps.len(){
-        writeln!(f, r#"    .string "{}""#, apps[i])?;
+    for config in configs.iter(){
+        writeln!(f, r#"    .string {}"#, asm_string(&config.name))?;
     }
 
-    for (idx,app) in apps.iter().enumerate(){
-        println!("app_{}:{}",idx,app);
+    for (idx,config) in configs.iter().enumerate(){
+        println!("app_{}:{}",idx,config.name);
         writeln!(
             f,
             r#"    .section .data
@@ -51,11 +288,11 @@ _app_names:"#)?;
     .global app_{0}_end
     .align 3
 app_{0}_start:
-    .incbin "{2}{1}.bin"
+    .incbin {1}
 app_{0}_end:
             "#,
-            idx,app,TARGET_PATH
+            idx,asm_string(&format!("{}{}", TARGET_PATH, config.name))
         )?;
     }
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,94 +1,278 @@
-// 在trap时实现用户栈和内核栈的转换（trap.S）
+// 在trap时通过跳板页切换地址空间（trap.S）
+// 每个app拥有独立的地址空间，app按ELF程序头加载到其中，内核栈位于内核地址空间的跳板页之下
 
+use crate::config::*;
+use crate::mm::{
+    print_frame_stats, print_swap_stats, translated_byte_buffer, FaultStats, LoadError, MemorySet, UserLayout,
+    KERNEL_SPACE,
+};
 use crate::sync::UPSafeCell;
-use crate::trap::TrapContext;
-use lazy_static::*; 
-
-const MAX_APPS: usize = 16;
-const USER_STACK_SIZE: usize = 4096 * 2;    // 8K
-const KERNEL_STACK_SIZE: usize = 4096 * 2;
-
-// for apps settings
-const APP_BASE_ADDRESS: usize = 0x80400000;
-const APP_SIZE_LIMIT: usize = 0x20000;
+use crate::task::{current_app, current_cpu_time_us, current_fault_stats, current_is_forked};
+use crate::timer::get_time_us;
+use crate::trap::{trap_handler, TrapContext};
+use alloc::vec;
+use alloc::vec::Vec;
+use lazy_static::*;
 
-#[repr(align(4096))]
-pub struct UserStack{
-    stack: [u8;USER_STACK_SIZE],
+// app的结束状态，在batch结束时汇总
+#[derive(Copy, Clone, PartialEq)]
+pub enum AppExit{
+    Running,
+    Exited(i32),
+    Killed(KillInfo),
 }
 
-#[repr(align(4096))]
-struct KernelStack{
-    stack: [u8;KERNEL_STACK_SIZE],
+// app被kill的原因，fault时附带 stval/sepc
+#[derive(Copy, Clone, PartialEq)]
+pub struct KillInfo{
+    pub reason: &'static str,
+    pub stval: usize,
+    pub sepc: usize,
 }
 
-static KERNEL_STACK: KernelStack = KernelStack {
-    stack: [0; KERNEL_STACK_SIZE],
-};
-pub static USER_STACK: UserStack = UserStack {
-    stack: [0; USER_STACK_SIZE],
-};
-
-impl UserStack{
-    pub fn get_sp(&self) -> usize{
-        self.stack.as_ptr() as usize + USER_STACK_SIZE
-    }
+// app每次运行（启动时的一次和之后每次sys_spawn）一条记录，时间单位为us
+#[derive(Copy, Clone)]
+struct AppRecord{
+    app_id: usize,
+    exit: AppExit,
+    start_us: Option<usize>,
+    end_us: usize,
+    // fork出的任务中被kill或以非0退出的个数，不为0时app不通过
+    failed_forks: usize,
+    // app及其fork出的任务的缺页次数，包括内核代替app访问用户内存时的缺页
+    minor_faults: usize,
+    major_faults: usize,
+    // 是否已经报告过该app调用了未知的系统调用，每个app只报告一次
+    unknown_syscall_noted: bool,
 }
-impl KernelStack{
-    fn get_sp(&self) -> usize{
-        self.stack.as_ptr() as usize + KERNEL_STACK_SIZE
-    }
-    fn push_context(&self, cx: TrapContext) -> &mut TrapContext{
-        let cx_ptr = (self.get_sp() - core::mem::size_of::<TrapContext>()) as *mut TrapContext;
-        unsafe{
-            *cx_ptr = cx;
+
+impl AppRecord{
+    fn new(app_id: usize) -> Self{
+        Self{
+            app_id,
+            exit: AppExit::Running,
+            start_us: None,
+            end_us: 0,
+            failed_forks: 0,
+            minor_faults: 0,
+            major_faults: 0,
+            unknown_syscall_noted: false,
         }
-        unsafe{cx_ptr.as_mut().unwrap()}
+    }
+    fn elapsed_us(&self) -> usize{
+        self.start_us.map_or(0, |start| self.end_us - start)
     }
 }
 
+// apps/batch.toml 中每个app的设置，对应build.rs生成的 _app_manifest 表中的一项
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct ManifestEntry{
+    // 0表示使用 DEFAULT_TIME_LIMIT_MS
+    time_limit_ms: usize,
+    expected_exit_code: isize,
+    // 期望被kill的原因，指向以'\0'结尾的字符串，0表示期望正常退出
+    expected_kill: usize,
+    // argc 个连续的以'\0'结尾的字符串
+    argc: usize,
+    args: usize,
+}
+
 struct AppManager{
     num_app: usize,
-    cur_app: usize,
-    app_start: [usize;MAX_APPS+1],
+    // num_app + 1 项，app_i 的数据位于 [app_start[i], app_start[i+1])
+    app_start: Vec<usize>,
+    app_names: Vec<&'static str>,
+    manifest: Vec<ManifestEntry>,
+    // 前 num_app 项是每个app启动时的运行，spawn的运行依次追加在后面
+    records: Vec<AppRecord>,
+    // 每个app最近一次运行在 records 中的下标
+    current_run: Vec<usize>,
+}
+
+// 读取以'\0'结尾的字符串，返回该字符串和它之后的地址
+unsafe fn read_c_str(ptr: *const u8) -> (&'static str, *const u8){
+    let mut end = ptr;
+    while end.read_volatile() != b'\0' {
+        end = end.add(1);
+    }
+    let len = end as usize - ptr as usize;
+    (core::str::from_utf8(core::slice::from_raw_parts(ptr, len)).unwrap(), end.add(1))
 }
 
 impl AppManager{
-    pub fn get_current_app(&self) -> usize{
-        self.cur_app
+    pub fn get_num_app(&self) -> usize{
+        self.num_app
+    }
+    // app最近一次运行的记录
+    fn record_mut(&mut self, app_id: usize) -> &mut AppRecord{
+        let run = self.current_run[app_id];
+        &mut self.records[run]
     }
-    pub fn move_to_next_app(&mut self){
-        self.cur_app += 1;
+    pub fn mark_started(&mut self, app_id: usize){
+        let record = self.record_mut(app_id);
+        if record.start_us.is_none() {
+            record.start_us = Some(get_time_us());
+        }
+    }
+    pub fn set_exit(&mut self, app_id: usize, exit: AppExit){
+        let record = self.record_mut(app_id);
+        record.exit = exit;
+        record.end_us = get_time_us();
+    }
+    // fork出的任务结束，只记录它是否失败
+    pub fn set_fork_exit(&mut self, app_id: usize, exit: AppExit){
+        if exit != AppExit::Exited(0) {
+            self.record_mut(app_id).failed_forks += 1;
+        }
+    }
+    pub fn add_fault_stats(&mut self, app_id: usize, stats: FaultStats){
+        let record = self.record_mut(app_id);
+        record.minor_faults += stats.minor;
+        record.major_faults += stats.major;
+    }
+    pub fn time_limit_us(&self, app_id: usize) -> usize{
+        match self.manifest[app_id].time_limit_ms {
+            0 => DEFAULT_TIME_LIMIT_MS * 1000,
+            ms => ms * 1000,
+        }
+    }
+    pub fn expected_kill(&self, app_id: usize) -> Option<&'static str>{
+        match self.manifest[app_id].expected_kill {
+            0 => None,
+            ptr => Some(unsafe{ read_c_str(ptr as *const u8).0 }),
+        }
+    }
+    // 与manifest中期望的结果比较，spawn的运行与启动时的运行有相同的期望
+    fn passed(&self, record: &AppRecord) -> bool{
+        if record.failed_forks != 0 {
+            return false;
+        }
+        match (record.exit, self.expected_kill(record.app_id)) {
+            (AppExit::Exited(code), None) => code as isize == self.manifest[record.app_id].expected_exit_code,
+            (AppExit::Killed(info), Some(reason)) => info.reason == reason,
+            _ => false,
+        }
+    }
+    pub fn num_failed(&self) -> usize{
+        self.records.iter().filter(|record| !self.passed(record)).count()
+    }
+    // 每行以 [report] 开头，字段为 key=value，方便CI grep。每次运行一行，run=0 是启动时的运行，
+    // 之后每次spawn加1
+    pub fn print_report(&self){
+        println!("[report] begin num_app={} num_runs={}",self.num_app,self.records.len());
+        let mut runs = vec![0; self.num_app];
+        for record in self.records.iter(){
+            let i = record.app_id;
+            print!("[report] id={} name={} run={} ",i,self.app_names[i],runs[i]);
+            runs[i] += 1;
+            match record.exit {
+                AppExit::Exited(code) => {
+                    print!("status=exited code={} reason=- stval=- sepc=-",code);
+                },
+                AppExit::Killed(info) => {
+                    print!(
+                        "status=killed code=- reason={} stval={:#x} sepc={:#x}",
+                        info.reason,info.stval,info.sepc
+                    );
+                },
+                AppExit::Running => {
+                    print!("status=unfinished code=- reason=- stval=- sepc=-");
+                },
+            }
+            match self.expected_kill(i) {
+                Some(reason) => {
+                    print!(" expected={}",reason);
+                },
+                None => {
+                    print!(" expected={}",self.manifest[i].expected_exit_code);
+                },
+            }
+            let result = if self.passed(record) { "pass" } else { "fail" };
+            println!(
+                " failed_forks={} minor_faults={} major_faults={} time_us={} result={}",
+                record.failed_forks,record.minor_faults,record.major_faults,record.elapsed_us(),result
+            );
+        }
+        let failed = self.num_failed();
+        println!("[report] end passed={} failed={}",self.records.len() - failed,failed);
+    }
+    // 按名字查找app编号
+    pub fn find_app(&self, name: &str) -> Option<usize>{
+        self.app_names.iter().position(|n| *n == name)
+    }
+    // 之后的结果记录在新的一次运行中，之前的运行的记录保留在报告里
+    pub fn new_run(&mut self, app_id: usize){
+        self.current_run[app_id] = self.records.len();
+        self.records.push(AppRecord::new(app_id));
     }
     pub fn print_app(&self){
         println!("[kernel] num_app = {}",self.num_app);
         for i in 0..self.num_app{
-            // this is why MAX_APPS+1 , to store both bgin and end
-            println!("[kernel] app_{}[{:x},{:x}]",i,self.app_start[i],self.app_start[i+1]);
+            println!("[kernel] app_{} {} [{:x},{:x}]",i,self.app_names[i],self.app_start[i],self.app_start[i+1]);
         }
     }
-    pub unsafe fn load_app(&self, app_id: usize){
-        if app_id >= MAX_APPS {
-            println!("Apps Complete!");
-            #[cfg(feature = "board_qemu")]
-            use crate::board::QEMUExit;
-            #[cfg(feature = "board_qemu")]
-            crate::board::QEMU_EXIT_HANDLE.exit_success();
+    pub fn app_data(&self, app_id: usize) -> &'static [u8]{
+        unsafe{
+            core::slice::from_raw_parts(
+                self.app_start[app_id] as *const u8,
+                self.app_start[app_id+1] - self.app_start[app_id]
+            )
         }
-        println!("[Kernel] load app_{}",app_id);
-        // 加载app的数据，到APP_BASE_ADDRESS
-        core::slice::from_raw_parts_mut(APP_BASE_ADDRESS as *mut u8,APP_SIZE_LIMIT).fill(0);
-        let app_src = core::slice::from_raw_parts_mut(
-            self.app_start[app_id] as *mut u8,
-            self.app_start[app_id+1] - self.app_start[app_id]
-        );
-        let app_dst = core::slice::from_raw_parts_mut(APP_BASE_ADDRESS as *mut u8,app_src.len());
-        // OK loaded
-        app_dst.copy_from_slice(app_src);
     }
+    fn set_load_out_of_memory(&mut self, app_id: usize){
+        println!("[kernel] no free frame to load app_{}, kernel rejected it.",app_id);
+        self.set_exit(app_id, AppExit::Killed(KillInfo{reason: "OutOfMemory", stval: 0, sepc: 0}));
+    }
+    // 把参数字符串和以0结尾的argv数组放到app的用户栈顶，返回 (argc, argv)，argv 即新的栈顶；
+    // 没有空闲页帧分配用户栈的页时返回 None
+    pub fn push_args(&self, app_id: usize, memory_set: &mut MemorySet, user_sp: usize) -> Option<(usize, usize)>{
+        let entry = &self.manifest[app_id];
+        let args_start = entry.args as *const u8;
+        let mut args_end = args_start;
+        for _ in 0..entry.argc{
+            args_end = unsafe{ read_c_str(args_end).1 };
+        }
+        let args = unsafe{
+            core::slice::from_raw_parts(args_start, args_end as usize - args_start as usize)
+        };
+        let str_base = user_sp - args.len();
+        let argv_base = (str_base - (entry.argc + 1) * core::mem::size_of::<usize>()) & !0xf;
+        // 每个参数从上一个'\0'之后开始
+        let mut argv: Vec<usize> = Vec::new();
+        let mut arg_start = 0;
+        for (i, c) in args.iter().enumerate(){
+            if *c == b'\0' {
+                argv.push(str_base + arg_start);
+                arg_start = i + 1;
+            }
+        }
+        argv.push(0);
+        let argv_bytes: Vec<u8> = argv.iter().flat_map(|ptr| ptr.to_ne_bytes()).collect();
+        if !copy_to_app(memory_set, str_base, args) || !copy_to_app(memory_set, argv_base, &argv_bytes) {
+            return None;
+        }
+        Some((entry.argc, argv_base))
+    }
+}
+
+// 写入app地址空间中 [va, va + data.len())，其中有页无法分配时返回false
+fn copy_to_app(memory_set: &mut MemorySet, va: usize, data: &[u8]) -> bool{
+    if !memory_set.populate(va, data.len(), true) {
+        return false;
+    }
+    let buffers = match translated_byte_buffer(memory_set.token(), va as *const u8, data.len(), true) {
+        Some(buffers) => buffers,
+        None => return false,
+    };
+    let mut start = 0;
+    for buffer in buffers{
+        buffer.copy_from_slice(&data[start..start + buffer.len()]);
+        start += buffer.len();
+    }
+    true
 }
 
- 
 lazy_static!{
     static ref APP_MANAGER: UPSafeCell<AppManager> = unsafe{
         UPSafeCell::new(
@@ -96,21 +280,34 @@ lazy_static!{
                 extern "C" {fn _num_app();}
                 let num_app_ptr = _num_app as usize as *mut usize;
                 let num_app = num_app_ptr.read_volatile();
-                let mut app_start: [usize;MAX_APPS+1] = [0;MAX_APPS+1];
-                let app_start_raw: &[usize] = core::slice::from_raw_parts_mut(num_app_ptr.add(1),num_app+1);
-                app_start[..=num_app].copy_from_slice(app_start_raw);
+                let app_start = core::slice::from_raw_parts(num_app_ptr.add(1),num_app+1).to_vec();
+                // _app_names: num_app 个以'\0'结尾的字符串，由build.rs生成
+                extern "C" {fn _app_names();}
+                let mut app_names: Vec<&'static str> = Vec::with_capacity(num_app);
+                let mut name_ptr = _app_names as usize as *const u8;
+                for _ in 0..num_app{
+                    let (name, next) = read_c_str(name_ptr);
+                    app_names.push(name);
+                    name_ptr = next;
+                }
+                // _app_manifest: 每个app一项 ManifestEntry
+                extern "C" {fn _app_manifest();}
+                let manifest = core::slice::from_raw_parts(
+                    _app_manifest as usize as *const ManifestEntry,num_app
+                ).to_vec();
                 AppManager{
                     num_app,
-                    cur_app: 0,
                     app_start,
+                    app_names,
+                    manifest,
+                    records: (0..num_app).map(AppRecord::new).collect(),
+                    current_run: (0..num_app).collect(),
                 }
             }
         )
     };
 }
 
-
-
 //interfaces
 
 pub fn init(){
@@ -122,29 +319,132 @@ pub fn print_app_info(){
     APP_MANAGER.exclusive_access().print_app();
 }
 
-// 现阶段在app执行结束或者产生fault时进行调用。
-pub fn run_next_app()->!{
-    // 加载下一个app，通过设置上下文调用__restore，返回用户态执行
+pub fn get_num_app() -> usize{
+    APP_MANAGER.exclusive_access().get_num_app()
+}
+
+pub fn find_app(name: &str) -> Option<usize>{
+    APP_MANAGER.exclusive_access().find_app(name)
+}
+
+// 当前app的名字，用于系统调用跟踪
+#[cfg(feature = "trace")]
+pub fn current_app_name() -> &'static str{
+    APP_MANAGER.exclusive_access().app_names[current_app()]
+}
+
+// 已结束的app被再次运行（sys_spawn），为这次运行新建一条记录
+pub fn new_app_run(app_id: usize){
+    APP_MANAGER.exclusive_access().new_run(app_id);
+}
+
+// 为app建立地址空间：按app的ELF文件加载各个段，把参数放到用户栈顶，
+// 返回地址空间、app的初始TrapContext和用户栈、堆的位置，参数通过 a0 = argc, a1 = argv 传给 apps_lib 的 _start。
+// ELF文件无效时app不能运行，记录为被kill（InvalidElf）并返回 None；没有足够的空闲页帧时同样返回 None，记录为 OutOfMemory
+pub fn load_app(app_id: usize) -> Option<(MemorySet, TrapContext, UserLayout)>{
     let mut app_manager = APP_MANAGER.exclusive_access();
-    let cur_app = app_manager.get_current_app();
-    unsafe{
-        app_manager.load_app(cur_app);
-    }
-    app_manager.move_to_next_app();
-    drop(app_manager);
+    let (mut memory_set, layout, entry) = match MemorySet::from_elf(app_manager.app_data(app_id)) {
+        Ok(loaded) => loaded,
+        Err(LoadError::InvalidElf(msg)) => {
+            println!("[kernel] app_{} is not a valid ELF file: {}, kernel rejected it.",app_id,msg);
+            app_manager.set_exit(app_id, AppExit::Killed(KillInfo{reason: "InvalidElf", stval: 0, sepc: 0}));
+            return None;
+        },
+        Err(LoadError::NoMemory) => {
+            app_manager.set_load_out_of_memory(app_id);
+            return None;
+        },
+    };
+    println!("[kernel] load app_{}, entry = {:#x}",app_id,entry);
+    let (argc, argv) = match app_manager.push_args(app_id, &mut memory_set, layout.user_stack_top) {
+        Some(args) => args,
+        None => {
+            app_manager.set_load_out_of_memory(app_id);
+            return None;
+        },
+    };
+    let mut cx = TrapContext::app_init_context(
+        entry,
+        argv,
+        KERNEL_SPACE.exclusive_access().token(),
+        kernel_stack_position(app_id).1,
+        trap_handler as usize,
+    );
+    cx.reg[10] = argc;
+    cx.reg[11] = argv;
+    Some((memory_set, cx, layout))
+}
 
-    extern "C"{
-        fn __restore(cx_addr: usize);
+// app第一次被调度运行，记录开始时间
+pub fn mark_app_started(app_id: usize){
+    APP_MANAGER.exclusive_access().mark_started(app_id);
+}
+
+// 记录当前任务的结束状态，app的结果只取决于它最初的任务
+fn set_current_exit(exit: AppExit){
+    let mut app_manager = APP_MANAGER.exclusive_access();
+    app_manager.add_fault_stats(current_app(), current_fault_stats());
+    if current_is_forked() {
+        if let AppExit::Exited(code) = exit {
+            println!("[kernel] forked task of app_{} exited with code {}", current_app(), code);
+        }
+        app_manager.set_fork_exit(current_app(), exit);
+    } else {
+        app_manager.set_exit(current_app(), exit);
     }
-    unsafe{
-        __restore(KERNEL_STACK.push_context(
-            TrapContext::app_init_context(
-                APP_BASE_ADDRESS,
-                USER_STACK.get_sp(),
-                )
-            ) as *const _ as usize
-        );
+}
+
+// 当前app调用了未知的系统调用（返回-ENOSYS），每个app只输出一次
+pub fn note_unknown_syscall(syscall_id: usize){
+    let mut app_manager = APP_MANAGER.exclusive_access();
+    let record = app_manager.record_mut(current_app());
+    if !record.unknown_syscall_noted {
+        record.unknown_syscall_noted = true;
+        println!("[kernel] app_{} called unsupported syscall {}, returning -ENOSYS", current_app(), syscall_id);
     }
-    panic!("Unreachable in batch::run_current_app!");
+}
+
+// sys_exit: 记录退出码，运行下一个app
+pub fn exit_current_app(exit_code: i32) -> !{
+    set_current_exit(AppExit::Exited(exit_code));
+    run_next_app()
+}
+
+// 当前app使用的CPU时间是否超过限制，在时钟中断中检查
+pub fn current_time_limit_exceeded() -> bool{
+    current_cpu_time_us() > APP_MANAGER.exclusive_access().time_limit_us(current_app())
+}
 
-}
\ No newline at end of file
+// app产生fault，被内核kill
+pub fn kill_current_app(reason: &'static str, stval: usize, sepc: usize) -> !{
+    set_current_exit(AppExit::Killed(KillInfo{reason, stval, sepc}));
+    run_next_app()
+}
+
+// 现阶段在app执行结束或者产生fault时进行调用。
+pub fn run_next_app()->!{
+    // 当前app结束，切换到下一个就绪的app
+    crate::task::exit_current_and_run_next();
+    panic!("Unreachable in batch::run_next_app!");
+}
+
+// 所有app都已结束：输出汇总，只有每次运行的结果都符合manifest的期望时qemu才以成功退出
+pub fn finish_batch() -> !{
+    let app_manager = APP_MANAGER.exclusive_access();
+    app_manager.print_report();
+    print_frame_stats();
+    print_swap_stats();
+    let all_succeeded = app_manager.num_failed() == 0;
+    drop(app_manager);
+    #[cfg(feature = "qemu")]
+    {
+        use crate::board::QEMUExit;
+        if all_succeeded {
+            crate::board::QEMU_EXIT_HANDLE.exit_success();
+        } else {
+            crate::board::QEMU_EXIT_HANDLE.exit_failure();
+        }
+    }
+    #[cfg(not(feature = "qemu"))]
+    panic!("Batch finished, all apps succeeded: {}", all_succeeded);
+}
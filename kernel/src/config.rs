@@ -1,4 +1,49 @@
+// 用户栈的大小上限，其中的页在第一次被访问时才分配
+pub const USER_STACK_SIZE: usize = 4096 * 16;   // 64K
+// 用户堆的大小上限，堆可以增长到的区域不能被mmap
+pub const USER_HEAP_SIZE: usize = 0x100_0000;   // 16M
+pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
+pub const KERNEL_HEAP_SIZE: usize = 0x20_0000;
+// 物理内存 [ekernel, MEMORY_END) 由页帧分配器管理
+pub const MEMORY_END: usize = 0x8100_0000;
+pub const PAGE_SIZE: usize = 0x1000;
+pub const PAGE_SIZE_BITS: usize = 0xc;
 
+// 用户程序只使用虚拟地址空间的低半部分 [0, USER_SPACE_END)
+pub const USER_SPACE_END: usize = 1 << 38;
+
+// 每个地址空间的最高一页是跳板页，app地址空间中它下面一页存放TrapContext
+pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
+pub const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
+
+// 内核地址空间中编号为 task_id 的任务的内核栈 [bottom, top)，位于跳板页之下，相邻的内核栈之间有一个guard page
+pub fn kernel_stack_position(task_id: usize) -> (usize, usize){
+    let top = TRAMPOLINE - task_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
+    let bottom = top - KERNEL_STACK_SIZE;
+    (bottom, top)
+}
+
+// CPU time limit of each app, can be overridden by apps/batch.toml
+pub const DEFAULT_TIME_LIMIT_MS: usize = 10_000;
+
+// stride scheduling
+pub const BIG_STRIDE: usize = 0x10000;
+pub const DEFAULT_PRIORITY: usize = 16;
+pub const MIN_PRIORITY: usize = 2;
+// 更大的优先级使 BIG_STRIDE / priority 为0，pass不再增长
+pub const MAX_PRIORITY: usize = BIG_STRIDE;
 
 #[cfg(feature="qemu")]
 pub const CLOCK_FREQ: usize = 12500000;
+
+// 设备的MMIO区域 (base, size)，在内核地址空间中恒等映射
+#[cfg(feature="qemu")]
+pub const MMIO: &[(usize, usize)] = &[
+    (0x0010_0000, 0x00_2000), // VIRT_TEST，用于退出qemu
+    (0x1000_1000, 0x00_1000), // VIRTIO0，用作交换区的块设备
+];
+#[cfg(feature="qemu")]
+pub const VIRTIO0: usize = 0x1000_1000;
+
+// 每个地址空间中最多驻留的按需分配的页（用户栈、堆和mmap），超过时按页面置换策略换出到交换区
+pub const USER_RESIDENT_PAGES: usize = 256;
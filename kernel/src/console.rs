@@ -1,13 +1,13 @@
 use crate::sbi::console_putchar;
+use alloc::vec::Vec;
 use core::fmt::{self, Write};
 
 struct Stdout;
 
 impl Write for Stdout{
+    // 按字节输出UTF-8编码，由终端负责解码
     fn write_str(&mut self , s : &str) -> fmt::Result{
-        for c in s.chars(){
-            console_putchar(c as usize);
-        }
+        print_bytes(s.as_bytes());
         Ok(())
     }
 }
@@ -16,6 +16,50 @@ pub fn print(args : fmt::Arguments){
     Stdout.write_fmt(args).unwrap();
 }
 
+// 原样输出任意字节（如app通过 sys_write 写入的数据），不要求是UTF-8
+pub fn print_bytes(bytes: &[u8]){
+    for &byte in bytes{
+        console_putchar(byte as usize);
+    }
+}
+
+// 把若干段相连的字节按UTF-8输出，无效的字节序列输出为U+FFFD，一个字符可以跨越相邻的两段
+pub fn print_bytes_lossy<'a>(parts: impl Iterator<Item = &'a [u8]>){
+    let mut pending: Vec<u8> = Vec::new();
+    for part in parts{
+        pending.extend_from_slice(part);
+        let incomplete = print_valid_utf8(&pending);
+        pending.drain(..pending.len() - incomplete);
+    }
+    // 结尾不完整的字符
+    if !pending.is_empty() {
+        print_bytes("\u{FFFD}".as_bytes());
+    }
+}
+
+// 输出 bytes 中除末尾不完整的字符以外的部分，返回不完整的字符的字节数
+fn print_valid_utf8(mut bytes: &[u8]) -> usize{
+    loop {
+        match core::str::from_utf8(bytes) {
+            Ok(s) => {
+                print_bytes(s.as_bytes());
+                return 0;
+            },
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                print_bytes(valid);
+                match e.error_len() {
+                    Some(len) => {
+                        print_bytes("\u{FFFD}".as_bytes());
+                        bytes = &rest[len..];
+                    },
+                    None => return rest.len(),
+                }
+            },
+        }
+    }
+}
+
 /// print string macro
 #[macro_export]
 macro_rules! print {
@@ -0,0 +1,13 @@
+// 设备驱动，目前只有用作交换区的 virtio 块设备
+
+mod virtio_blk;
+
+pub use virtio_blk::{VirtIOBlock, BLOCK_SIZE};
+
+use crate::sync::UPSafeCell;
+
+lazy_static!{
+    // 没有找到块设备时为 None
+    pub static ref BLOCK_DEVICE: Option<UPSafeCell<VirtIOBlock>> =
+        VirtIOBlock::probe().map(|blk| unsafe{ UPSafeCell::new(blk) });
+}
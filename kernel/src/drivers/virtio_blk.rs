@@ -0,0 +1,250 @@
+// virtio-mmio 块设备的轮询驱动，支持 legacy (version 1) 和 version 2 两种接口
+// 只使用一个请求队列，每次提交一个请求并忙等它完成，不使用中断
+
+use crate::config::{PAGE_SIZE, VIRTIO0};
+use alloc::alloc::{alloc_zeroed, Layout};
+use alloc::boxed::Box;
+use core::hint::spin_loop;
+use core::ptr::{addr_of, addr_of_mut, read_volatile, write_volatile};
+use core::sync::atomic::{fence, Ordering};
+
+pub const BLOCK_SIZE: usize = 512;
+
+// virtio-mmio 寄存器的偏移
+const MAGIC_VALUE: usize = 0x000;
+const VERSION: usize = 0x004;
+const DEVICE_ID: usize = 0x008;
+const DRIVER_FEATURES: usize = 0x020;
+const DRIVER_FEATURES_SEL: usize = 0x024;
+const GUEST_PAGE_SIZE: usize = 0x028;
+const QUEUE_SEL: usize = 0x030;
+const QUEUE_NUM_MAX: usize = 0x034;
+const QUEUE_NUM: usize = 0x038;
+const QUEUE_ALIGN: usize = 0x03c;
+const QUEUE_PFN: usize = 0x040;
+const QUEUE_READY: usize = 0x044;
+const QUEUE_NOTIFY: usize = 0x050;
+const INTERRUPT_STATUS: usize = 0x060;
+const INTERRUPT_ACK: usize = 0x064;
+const STATUS: usize = 0x070;
+const QUEUE_DESC_LOW: usize = 0x080;
+const QUEUE_DESC_HIGH: usize = 0x084;
+const QUEUE_DRIVER_LOW: usize = 0x090;
+const QUEUE_DRIVER_HIGH: usize = 0x094;
+const QUEUE_DEVICE_LOW: usize = 0x0a0;
+const QUEUE_DEVICE_HIGH: usize = 0x0a4;
+// 块设备的配置空间，开头是以扇区为单位的容量 (u64)
+const CONFIG_CAPACITY: usize = 0x100;
+
+const MAGIC: u32 = 0x7472_6976; // "virt"
+const DEVICE_ID_BLOCK: u32 = 2;
+
+const STATUS_ACKNOWLEDGE: u32 = 1;
+const STATUS_DRIVER: u32 = 2;
+const STATUS_DRIVER_OK: u32 = 4;
+const STATUS_FEATURES_OK: u32 = 8;
+// VIRTIO_F_VERSION_1 是第32位特性，即高32位特性字的第0位，version 2 的设备要求驱动接受它
+const FEATURE_VERSION_1_HIGH: u32 = 1;
+
+const DESC_F_NEXT: u16 = 1;
+const DESC_F_WRITE: u16 = 2;
+
+const REQ_TYPE_IN: u32 = 0;
+const REQ_TYPE_OUT: u32 = 1;
+
+// 一个请求用3个描述符：请求头、数据、状态
+const QUEUE_SIZE: usize = 4;
+
+#[repr(C)]
+struct Descriptor{
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+#[repr(C)]
+struct AvailRing{
+    flags: u16,
+    idx: u16,
+    ring: [u16; QUEUE_SIZE],
+    used_event: u16,
+}
+
+#[repr(C)]
+struct UsedElem{
+    id: u32,
+    len: u32,
+}
+
+// legacy 接口要求 used ring 从下一页开始
+#[repr(C, align(4096))]
+struct UsedRing{
+    flags: u16,
+    idx: u16,
+    ring: [UsedElem; QUEUE_SIZE],
+    avail_event: u16,
+}
+
+#[repr(C)]
+struct BlockRequest{
+    req_type: u32,
+    reserved: u32,
+    sector: u64,
+}
+
+// 设备直接访问这里的物理地址。它分配在内核堆上，内核堆在 .bss 中，是恒等映射的
+#[repr(C, align(4096))]
+struct VirtQueue{
+    desc: [Descriptor; QUEUE_SIZE],
+    avail: AvailRing,
+    header: BlockRequest,
+    status: u8,
+    used: UsedRing,
+}
+
+pub struct VirtIOBlock{
+    base: usize,
+    queue: Box<VirtQueue>,
+    // 以扇区为单位
+    capacity: usize,
+    last_used_idx: u16,
+}
+
+fn read_reg(base: usize, offset: usize) -> u32{
+    unsafe{ read_volatile((base + offset) as *const u32) }
+}
+
+fn write_reg(base: usize, offset: usize, value: u32){
+    unsafe{ write_volatile((base + offset) as *mut u32, value) }
+}
+
+impl VirtIOBlock{
+    // 初始化 VIRTIO0 处的块设备，qemu启动时没有挂载磁盘时返回 None
+    pub fn probe() -> Option<Self>{
+        let base = VIRTIO0;
+        if read_reg(base, MAGIC_VALUE) != MAGIC || read_reg(base, DEVICE_ID) != DEVICE_ID_BLOCK {
+            return None;
+        }
+        let legacy = match read_reg(base, VERSION) {
+            1 => true,
+            2 => false,
+            _ => return None,
+        };
+        write_reg(base, STATUS, 0);
+        let mut status = STATUS_ACKNOWLEDGE | STATUS_DRIVER;
+        write_reg(base, STATUS, status);
+        // 不使用任何可选特性
+        if legacy {
+            write_reg(base, DRIVER_FEATURES, 0);
+            write_reg(base, GUEST_PAGE_SIZE, PAGE_SIZE as u32);
+        } else {
+            write_reg(base, DRIVER_FEATURES_SEL, 1);
+            write_reg(base, DRIVER_FEATURES, FEATURE_VERSION_1_HIGH);
+            write_reg(base, DRIVER_FEATURES_SEL, 0);
+            write_reg(base, DRIVER_FEATURES, 0);
+            status |= STATUS_FEATURES_OK;
+            write_reg(base, STATUS, status);
+            if read_reg(base, STATUS) & STATUS_FEATURES_OK == 0 {
+                return None;
+            }
+        }
+        write_reg(base, QUEUE_SEL, 0);
+        if (read_reg(base, QUEUE_NUM_MAX) as usize) < QUEUE_SIZE {
+            return None;
+        }
+        write_reg(base, QUEUE_NUM, QUEUE_SIZE as u32);
+        // 直接在堆上清零分配，VirtQueue 有两页，不能先放在内核栈上
+        let queue = unsafe{
+            Box::from_raw(alloc_zeroed(Layout::new::<VirtQueue>()) as *mut VirtQueue)
+        };
+        if legacy {
+            write_reg(base, QUEUE_ALIGN, PAGE_SIZE as u32);
+            write_reg(base, QUEUE_PFN, (&*queue as *const VirtQueue as usize / PAGE_SIZE) as u32);
+        } else {
+            let regs = [
+                (QUEUE_DESC_LOW, QUEUE_DESC_HIGH, addr_of!(queue.desc) as usize),
+                (QUEUE_DRIVER_LOW, QUEUE_DRIVER_HIGH, addr_of!(queue.avail) as usize),
+                (QUEUE_DEVICE_LOW, QUEUE_DEVICE_HIGH, addr_of!(queue.used) as usize),
+            ];
+            for (low, high, addr) in regs{
+                write_reg(base, low, addr as u32);
+                write_reg(base, high, (addr >> 32) as u32);
+            }
+            write_reg(base, QUEUE_READY, 1);
+        }
+        status |= STATUS_DRIVER_OK;
+        write_reg(base, STATUS, status);
+        let capacity = read_reg(base, CONFIG_CAPACITY) as usize
+            | (read_reg(base, CONFIG_CAPACITY + 4) as usize) << 32;
+        Some(Self{
+            base,
+            queue,
+            capacity,
+            last_used_idx: 0,
+        })
+    }
+
+    pub fn capacity(&self) -> usize{
+        self.capacity
+    }
+
+    // 读写从 sector 开始的 buf.len() / BLOCK_SIZE 个扇区，buf 必须位于恒等映射的内存中（如物理页帧）
+    pub fn read_blocks(&mut self, sector: usize, buf: &mut [u8]) -> bool{
+        self.request(REQ_TYPE_IN, sector, buf.as_mut_ptr() as usize, buf.len())
+    }
+
+    pub fn write_blocks(&mut self, sector: usize, buf: &[u8]) -> bool{
+        self.request(REQ_TYPE_OUT, sector, buf.as_ptr() as usize, buf.len())
+    }
+
+    fn request(&mut self, req_type: u32, sector: usize, buf: usize, len: usize) -> bool{
+        assert!(len % BLOCK_SIZE == 0, "block request of {} bytes", len);
+        if sector + len / BLOCK_SIZE > self.capacity {
+            return false;
+        }
+        let queue = &mut *self.queue;
+        queue.header = BlockRequest{
+            req_type,
+            reserved: 0,
+            sector: sector as u64,
+        };
+        queue.status = 0xff;
+        // 读请求时数据由设备写入
+        let data_flags = if req_type == REQ_TYPE_IN { DESC_F_WRITE } else { 0 };
+        queue.desc[0] = Descriptor{
+            addr: addr_of!(queue.header) as u64,
+            len: core::mem::size_of::<BlockRequest>() as u32,
+            flags: DESC_F_NEXT,
+            next: 1,
+        };
+        queue.desc[1] = Descriptor{
+            addr: buf as u64,
+            len: len as u32,
+            flags: data_flags | DESC_F_NEXT,
+            next: 2,
+        };
+        queue.desc[2] = Descriptor{
+            addr: addr_of!(queue.status) as u64,
+            len: 1,
+            flags: DESC_F_WRITE,
+            next: 0,
+        };
+        let avail_idx = queue.avail.idx;
+        queue.avail.ring[avail_idx as usize % QUEUE_SIZE] = 0;
+        // 描述符必须在 avail.idx 之前对设备可见
+        fence(Ordering::SeqCst);
+        unsafe{
+            write_volatile(addr_of_mut!(queue.avail.idx), avail_idx.wrapping_add(1));
+        }
+        fence(Ordering::SeqCst);
+        write_reg(self.base, QUEUE_NOTIFY, 0);
+        while unsafe{ read_volatile(addr_of!(queue.used.idx)) } == self.last_used_idx {
+            spin_loop();
+        }
+        self.last_used_idx = self.last_used_idx.wrapping_add(1);
+        fence(Ordering::SeqCst);
+        write_reg(self.base, INTERRUPT_ACK, read_reg(self.base, INTERRUPT_STATUS));
+        unsafe{ read_volatile(addr_of!(queue.status)) == 0 }
+    }
+}
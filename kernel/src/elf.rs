@@ -0,0 +1,114 @@
+// 只支持加载app所需的ELF子集：RISC-V 64位小端的可执行文件，只使用其中的 PT_LOAD 段
+
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+const ET_EXEC: u16 = 2;
+const EM_RISCV: u16 = 243;
+
+const EHDR_SIZE: usize = 64;
+const PHDR_SIZE: usize = 56;
+
+pub const PT_LOAD: u32 = 1;
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+// 程序头中加载时用到的字段
+#[derive(Copy, Clone, Debug)]
+pub struct ProgramHeader{
+    pub p_type: u32,
+    pub flags: u32,
+    pub offset: usize,
+    pub vaddr: usize,
+    pub file_size: usize,
+    pub mem_size: usize,
+}
+
+pub struct ElfFile<'a>{
+    data: &'a [u8],
+    pub entry: usize,
+    ph_offset: usize,
+    ph_num: usize,
+}
+
+// ELF中的字段不一定按自身大小对齐，逐字节读取
+fn read_u16(data: &[u8], offset: usize) -> u16{
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32{
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> usize{
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap()) as usize
+}
+
+impl<'a> ElfFile<'a>{
+    // 检查ELF头和每个程序头，之后访问程序头和段的内容都不会越界
+    pub fn parse(data: &'a [u8]) -> Result<Self, &'static str>{
+        if data.len() < EHDR_SIZE || data[..4] != ELF_MAGIC {
+            return Err("not an ELF file");
+        }
+        if data[4] != ELFCLASS64 || data[5] != ELFDATA2LSB || data[6] != EV_CURRENT {
+            return Err("not a 64-bit little-endian ELF file");
+        }
+        if read_u16(data, 16) != ET_EXEC {
+            return Err("not an executable");
+        }
+        if read_u16(data, 18) != EM_RISCV {
+            return Err("not a RISC-V executable");
+        }
+        let ph_offset = read_u64(data, 32);
+        let ph_num = read_u16(data, 56) as usize;
+        if ph_num != 0 && read_u16(data, 54) as usize != PHDR_SIZE {
+            return Err("bad program header size");
+        }
+        let ph_end = ph_num.checked_mul(PHDR_SIZE).and_then(|size| size.checked_add(ph_offset));
+        if ph_end.map_or(true, |end| end > data.len()) {
+            return Err("program headers out of file");
+        }
+        let elf = Self{
+            data,
+            entry: read_u64(data, 24),
+            ph_offset,
+            ph_num,
+        };
+        for ph in elf.program_headers(){
+            if ph.p_type != PT_LOAD {
+                continue;
+            }
+            if ph.file_size > ph.mem_size {
+                return Err("segment file size larger than memory size");
+            }
+            if ph.offset.checked_add(ph.file_size).map_or(true, |end| end > data.len()) {
+                return Err("segment out of file");
+            }
+            if ph.vaddr.checked_add(ph.mem_size).is_none() {
+                return Err("segment address overflow");
+            }
+        }
+        Ok(elf)
+    }
+
+    pub fn program_headers(&self) -> impl Iterator<Item = ProgramHeader> + '_{
+        (0..self.ph_num).map(move |i| {
+            let ph = &self.data[self.ph_offset + i * PHDR_SIZE..];
+            ProgramHeader{
+                p_type: read_u32(ph, 0),
+                flags: read_u32(ph, 4),
+                offset: read_u64(ph, 8),
+                vaddr: read_u64(ph, 16),
+                file_size: read_u64(ph, 32),
+                mem_size: read_u64(ph, 40),
+            }
+        })
+    }
+
+    // 段在文件中的内容，.bss 等不在文件中的部分由加载者清零
+    pub fn segment_data(&self, ph: &ProgramHeader) -> &'a [u8]{
+        &self.data[ph.offset..ph.offset + ph.file_size]
+    }
+}
@@ -1,6 +1,7 @@
 #![no_std]
 #![no_main]
 #![feature(panic_info_message)]
+#![feature(alloc_error_handler)]
 
 #[macro_use]
 mod console;
@@ -13,10 +14,19 @@ mod sync;
 pub mod syscall;
 pub mod timer;
 pub mod config;
+pub mod task;
+pub mod mm;
+mod drivers;
+mod elf;
+
+extern crate alloc;
 
 #[macro_use]
 extern crate lazy_static;
 
+#[macro_use]
+extern crate bitflags;
+
 #[cfg(feature = "qemu")]
 #[path = "../board/qemu.rs"]
 mod board;
@@ -60,20 +70,16 @@ fn simpl_os_main() -> ! {
     );
     println!(".bss [{:#x}, {:#x})", sbss as usize, ebss as usize);
     println!("begin run some Apps here!");
+    mm::init();
+    mm::remap_test();
+    mm::frame_allocator_test();
+    mm::init_swap();
     trap::init();
     batch::init();
-    batch::run_next_app();
-
-    #[cfg(feature = "qemu")]
-    use crate::board::QEMUExit;
-
-    #[cfg(feature="qemu")]
-    crate::board::QEMU_EXIT_HANDLE.exit_success();
-
-    #[cfg(feature = "board_k210")]
-    panic!("Unreachable in rust_main!");
-
-    panic!("Should Shutdown!");
+    trap::enable_timer_interrupt();
+    timer::set_next_trigger();
+    // 所有app结束后由 batch::finish_batch 关机，不会返回
+    task::run_first_task();
 }
 
 fn welcome(){
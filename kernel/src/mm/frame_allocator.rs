@@ -0,0 +1,181 @@
+// 物理页帧分配器，管理 [ekernel, MEMORY_END) 之间的物理内存
+
+use super::{PhysAddr, PhysPageNum};
+use crate::config::MEMORY_END;
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Formatter};
+use lazy_static::*;
+
+// 物理页帧的所有权，drop时自动回收
+pub struct FrameTracker{
+    pub ppn: PhysPageNum,
+}
+
+impl FrameTracker{
+    pub fn new(ppn: PhysPageNum) -> Self{
+        // 分配到的页帧可能有残留数据，先清零
+        ppn.get_bytes_array().fill(0);
+        Self{ ppn }
+    }
+}
+
+impl Debug for FrameTracker{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result{
+        f.write_fmt(format_args!("FrameTracker:PPN={:#x}", self.ppn.0))
+    }
+}
+
+impl Drop for FrameTracker{
+    fn drop(&mut self){
+        frame_dealloc(self.ppn);
+    }
+}
+
+trait FrameAllocator{
+    fn new() -> Self;
+    fn alloc(&mut self) -> Option<PhysPageNum>;
+    fn dealloc(&mut self, ppn: PhysPageNum);
+}
+
+// 页帧分配的统计信息，以页为单位
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FrameStats{
+    pub total: usize,
+    // 当前已分配的页帧数和它的最大值
+    pub allocated: usize,
+    pub peak: usize,
+    pub alloc_count: usize,
+    pub dealloc_count: usize,
+    // 没有空闲页帧时的分配次数
+    pub failed_count: usize,
+}
+
+// [current, end) 是从未分配过的页帧，recycled 是回收的页帧
+pub struct StackFrameAllocator{
+    current: usize,
+    end: usize,
+    recycled: Vec<usize>,
+    stats: FrameStats,
+}
+
+impl StackFrameAllocator{
+    pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum){
+        self.current = l.0;
+        self.end = r.0;
+        self.stats.total = r.0 - l.0;
+    }
+    pub fn stats(&self) -> FrameStats{
+        self.stats
+    }
+}
+
+impl FrameAllocator for StackFrameAllocator{
+    fn new() -> Self{
+        Self{
+            current: 0,
+            end: 0,
+            recycled: Vec::new(),
+            stats: FrameStats::default(),
+        }
+    }
+    fn alloc(&mut self) -> Option<PhysPageNum>{
+        let ppn = if let Some(ppn) = self.recycled.pop() {
+            ppn
+        } else if self.current == self.end {
+            self.stats.failed_count += 1;
+            return None;
+        } else {
+            self.current += 1;
+            self.current - 1
+        };
+        self.stats.alloc_count += 1;
+        self.stats.allocated += 1;
+        self.stats.peak = self.stats.peak.max(self.stats.allocated);
+        Some(ppn.into())
+    }
+    fn dealloc(&mut self, ppn: PhysPageNum){
+        let ppn = ppn.0;
+        // validity check
+        if ppn >= self.current || self.recycled.iter().any(|&v| v == ppn) {
+            panic!("Frame ppn={:#x} has not been allocated!", ppn);
+        }
+        self.recycled.push(ppn);
+        self.stats.dealloc_count += 1;
+        self.stats.allocated -= 1;
+    }
+}
+
+type FrameAllocatorImpl = StackFrameAllocator;
+
+lazy_static!{
+    pub static ref FRAME_ALLOCATOR: UPSafeCell<FrameAllocatorImpl> =
+        unsafe{ UPSafeCell::new(FrameAllocatorImpl::new()) };
+}
+
+pub fn init_frame_allocator(){
+    extern "C" {
+        fn ekernel();
+    }
+    let start = PhysAddr::from(ekernel as usize).ceil();
+    let end = PhysAddr::from(MEMORY_END).floor();
+    FRAME_ALLOCATOR.exclusive_access().init(start, end);
+    println!(
+        "[kernel] frame allocator: {} frames [{:#x}, {:#x})",
+        end.0 - start.0, PhysAddr::from(start).0, PhysAddr::from(end).0
+    );
+}
+
+pub fn frame_alloc() -> Option<FrameTracker>{
+    FRAME_ALLOCATOR
+        .exclusive_access()
+        .alloc()
+        .map(FrameTracker::new)
+}
+
+fn frame_dealloc(ppn: PhysPageNum){
+    FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
+}
+
+pub fn frame_stats() -> FrameStats{
+    FRAME_ALLOCATOR.exclusive_access().stats()
+}
+
+pub fn print_frame_stats(){
+    let stats = frame_stats();
+    println!(
+        "[kernel] frames: total={} allocated={} peak={} allocs={} deallocs={} failed={}",
+        stats.total, stats.allocated, stats.peak, stats.alloc_count, stats.dealloc_count, stats.failed_count
+    );
+}
+
+// 分配一批页帧再全部释放，检查已分配的页帧数恢复原值（没有泄漏），且回收的页帧会被再次分配
+pub fn frame_allocator_test(){
+    const TEST_FRAMES: usize = 16;
+    let before = frame_stats();
+    let mut frames: Vec<FrameTracker> = Vec::new();
+    for _ in 0..TEST_FRAMES{
+        let frame = frame_alloc().unwrap();
+        assert!(frames.iter().all(|f| f.ppn != frame.ppn), "{:?} allocated twice", frame);
+        assert!(frame.ppn.get_bytes_array().iter().all(|b| *b == 0), "{:?} is not zeroed", frame);
+        frame.ppn.get_bytes_array().fill(0xa5);
+        frames.push(frame);
+    }
+    assert_eq!(frame_stats().allocated, before.allocated + TEST_FRAMES);
+    let mut freed: Vec<PhysPageNum> = frames.iter().map(|f| f.ppn).collect();
+    frames.clear();
+    assert_eq!(frame_stats().allocated, before.allocated);
+    for _ in 0..TEST_FRAMES{
+        let frame = frame_alloc().unwrap();
+        let idx = freed.iter().position(|ppn| *ppn == frame.ppn).expect("recycled frame is not reused");
+        freed.swap_remove(idx);
+        assert!(frame.ppn.get_bytes_array().iter().all(|b| *b == 0), "{:?} is not zeroed", frame);
+        frames.push(frame);
+    }
+    drop(frames);
+    let after = frame_stats();
+    assert_eq!(after.allocated, before.allocated);
+    assert_eq!(after.alloc_count - before.alloc_count, 2 * TEST_FRAMES);
+    assert_eq!(after.dealloc_count - before.dealloc_count, 2 * TEST_FRAMES);
+    println!("[kernel] frame_allocator_test passed!");
+}
@@ -0,0 +1,30 @@
+// 内核堆，位于 .bss 中的一段静态内存，使 Vec/BTreeMap 等 alloc 中的容器可用
+
+use crate::config::KERNEL_HEAP_SIZE;
+use core::alloc::Layout;
+use linked_list_allocator::LockedHeap;
+
+#[global_allocator]
+static HEAP_ALLOCATOR: LockedHeap = LockedHeap::empty();
+
+static mut HEAP_SPACE: [u8; KERNEL_HEAP_SIZE] = [0; KERNEL_HEAP_SIZE];
+
+pub fn init_heap(){
+    unsafe{
+        HEAP_ALLOCATOR
+            .lock()
+            .init(HEAP_SPACE.as_mut_ptr(), KERNEL_HEAP_SIZE);
+    }
+}
+
+// 堆空间不足（或碎片过多）时由 alloc 调用
+#[alloc_error_handler]
+pub fn handle_alloc_error(layout: Layout) -> !{
+    let heap = HEAP_ALLOCATOR.lock();
+    let (used, free) = (heap.used(), heap.free());
+    drop(heap);
+    panic!(
+        "Heap allocation error, size = {:#x}, align = {:#x}, heap used = {:#x}, free = {:#x}",
+        layout.size(), layout.align(), used, free
+    );
+}
@@ -0,0 +1,678 @@
+// 地址空间：一个页表和若干逻辑段（MapArea）
+
+use super::{frame_alloc, FrameTracker};
+use super::{PTEFlags, PageTable, PageTableEntry};
+use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
+use super::{StepByOne, VPNRange};
+use super::replace::{ActiveReplacer, Replacer};
+use super::swap::{swap_enabled, swap_in, swap_out, SwapSlot};
+use crate::elf::{ElfFile, PF_R, PF_W, PF_X, PT_LOAD};
+use crate::config::{
+    MEMORY_END, MMIO, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT, USER_RESIDENT_PAGES, USER_SPACE_END, USER_STACK_SIZE,
+};
+use core::cmp::{max, min};
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::arch::asm;
+use lazy_static::*;
+use riscv::register::satp;
+
+extern "C" {
+    fn stext();
+    fn etext();
+    fn srodata();
+    fn erodata();
+    fn sdata();
+    fn edata();
+    fn sbss_with_stack();
+    fn ebss();
+    fn ekernel();
+    fn strampoline();
+}
+
+lazy_static!{
+    // 内核地址空间
+    pub static ref KERNEL_SPACE: Arc<UPSafeCell<MemorySet>> =
+        Arc::new(unsafe{ UPSafeCell::new(MemorySet::new_kernel()) });
+}
+
+pub struct MemorySet{
+    page_table: PageTable,
+    areas: Vec<MapArea>,
+    // 驻留的按需分配的页，超过 USER_RESIDENT_PAGES 时由置换策略选出要换出的页
+    resident: ActiveReplacer,
+    fault_stats: FaultStats,
+}
+
+// 缺页次数：minor 不需要读交换区（按需分配、写时复制），major 需要从交换区换入
+#[derive(Copy, Clone, Default)]
+pub struct FaultStats{
+    pub minor: usize,
+    pub major: usize,
+}
+
+// from_elf 失败的原因
+pub enum LoadError{
+    InvalidElf(&'static str),
+    // 没有空闲页帧
+    NoMemory,
+}
+
+impl From<&'static str> for LoadError{
+    fn from(msg: &'static str) -> Self{
+        LoadError::InvalidElf(msg)
+    }
+}
+
+// from_elf 建立的用户地址空间中用户栈和堆的位置，用户栈之下是一个guard page
+#[derive(Copy, Clone)]
+pub struct UserLayout{
+    pub user_stack_bottom: usize,
+    pub user_stack_top: usize,
+    pub heap_bottom: usize,
+}
+
+impl MemorySet{
+    // 没有空闲页帧存放根页表时返回 None
+    pub fn new_bare() -> Option<Self>{
+        Some(Self{
+            page_table: PageTable::new()?,
+            areas: Vec::new(),
+            resident: ActiveReplacer::new(),
+            fault_stats: FaultStats::default(),
+        })
+    }
+    pub fn token(&self) -> usize{
+        self.page_table.token()
+    }
+    // 假设新的逻辑段与已有的不重叠，没有足够的空闲页帧时不插入并返回false
+    pub fn insert_framed_area(&mut self, start_va: VirtAddr, end_va: VirtAddr, permission: MapPermission) -> bool{
+        self.push(MapArea::new(start_va, end_va, MapType::Framed, permission), None)
+    }
+    // 其中的页在第一次被访问时才分配，同样假设与已有的逻辑段不重叠
+    pub fn insert_lazy_area(&mut self, start_va: VirtAddr, end_va: VirtAddr, permission: MapPermission){
+        self.push(MapArea::new(start_va, end_va, MapType::Lazy, permission), None);
+    }
+    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) -> bool{
+        if !map_area.map(&mut self.page_table) {
+            return false;
+        }
+        if let Some(data) = data {
+            map_area.copy_data(&mut self.page_table, 0, data);
+        }
+        self.areas.push(map_area);
+        true
+    }
+    // 跳板页不属于任何逻辑段，内核和所有app的地址空间都把它映射到最高的虚拟页
+    fn map_trampoline(&mut self) -> bool{
+        self.page_table.map(
+            VirtAddr::from(TRAMPOLINE).into(),
+            PhysAddr::from(strampoline as usize).into(),
+            PTEFlags::R | PTEFlags::X,
+        )
+    }
+    // 内核的各个段和可分配的物理内存都是恒等映射，启动时没有足够的空闲页帧建立内核页表则无法继续
+    pub fn new_kernel() -> Self{
+        let mut memory_set = Self::new_bare().expect("no free frame for the kernel page table");
+        assert!(memory_set.map_trampoline(), "no free frame for the kernel page table");
+        let mut areas: Vec<(VirtAddr, VirtAddr, MapPermission)> = vec![
+            ((stext as usize).into(), (etext as usize).into(), MapPermission::R | MapPermission::X),
+            ((srodata as usize).into(), (erodata as usize).into(), MapPermission::R),
+            ((sdata as usize).into(), (edata as usize).into(), MapPermission::R | MapPermission::W),
+            ((sbss_with_stack as usize).into(), (ebss as usize).into(), MapPermission::R | MapPermission::W),
+            ((ekernel as usize).into(), MEMORY_END.into(), MapPermission::R | MapPermission::W),
+        ];
+        // 设备的MMIO区域
+        for &(base, size) in MMIO{
+            areas.push((base.into(), (base + size).into(), MapPermission::R | MapPermission::W));
+        }
+        for (start_va, end_va, permission) in areas{
+            let area = MapArea::new(start_va, end_va, MapType::Identical, permission);
+            assert!(memory_set.push(area, None), "no free frame for the kernel page table");
+        }
+        memory_set
+    }
+    // 按app的ELF文件中的 PT_LOAD 段建立地址空间，每个段按它的R/W/X标志映射，段中不在文件里的部分（.bss）为0。
+    // 最高的段之后是一个guard page和用户栈，再隔一个guard page是初始为空的堆（由 sbrk 扩展），
+    // 用户栈和堆都按需分配。返回地址空间、用户栈和堆的位置以及入口地址
+    pub fn from_elf(data: &[u8]) -> Result<(Self, UserLayout, usize), LoadError>{
+        let elf = ElfFile::parse(data)?;
+        let mut memory_set = Self::new_bare().ok_or(LoadError::NoMemory)?;
+        if !memory_set.map_trampoline() {
+            return Err(LoadError::NoMemory);
+        }
+        let mut image_end = VirtPageNum::from(0);
+        let mut entry_mapped = false;
+        for ph in elf.program_headers().filter(|ph| ph.p_type == PT_LOAD && ph.mem_size != 0){
+            let start_va = VirtAddr::from(ph.vaddr);
+            let end_va = VirtAddr::from(ph.vaddr + ph.mem_size);
+            if ph.vaddr + ph.mem_size > USER_SPACE_END {
+                return Err("segment outside of user space".into());
+            }
+            if memory_set.areas.iter().any(|area| area.overlaps(start_va.floor(), end_va.ceil())) {
+                return Err("segments overlap".into());
+            }
+            let mut map_perm = MapPermission::U;
+            if ph.flags & PF_R != 0 {
+                map_perm |= MapPermission::R;
+            }
+            if ph.flags & PF_W != 0 {
+                map_perm |= MapPermission::W;
+            }
+            if ph.flags & PF_X != 0 {
+                map_perm |= MapPermission::X;
+            }
+            if map_perm == MapPermission::U {
+                return Err("segment without any permission".into());
+            }
+            // 页表项中 W 不能单独出现
+            if map_perm.contains(MapPermission::W) {
+                map_perm |= MapPermission::R;
+            }
+            if map_perm.contains(MapPermission::X) && (ph.vaddr..ph.vaddr + ph.mem_size).contains(&elf.entry) {
+                entry_mapped = true;
+            }
+            if !memory_set.push(MapArea::new(start_va, end_va, MapType::Framed, map_perm), None) {
+                return Err(LoadError::NoMemory);
+            }
+            let area = memory_set.areas.last_mut().unwrap();
+            area.copy_data(&mut memory_set.page_table, start_va.page_offset(), elf.segment_data(&ph));
+            image_end = image_end.max(end_va.ceil());
+        }
+        if !entry_mapped {
+            return Err("entry point is not in an executable segment".into());
+        }
+        let user_stack_bottom = VirtAddr::from(image_end).0 + PAGE_SIZE;
+        let user_stack_top = user_stack_bottom + USER_STACK_SIZE;
+        memory_set.insert_lazy_area(
+            user_stack_bottom.into(),
+            user_stack_top.into(),
+            MapPermission::R | MapPermission::W | MapPermission::U,
+        );
+        let heap_bottom = user_stack_top + PAGE_SIZE;
+        memory_set.insert_lazy_area(
+            heap_bottom.into(),
+            heap_bottom.into(),
+            MapPermission::R | MapPermission::W | MapPermission::U,
+        );
+        // TrapContext 只由内核和跳板页中的代码访问
+        if !memory_set.insert_framed_area(TRAP_CONTEXT.into(), TRAMPOLINE.into(), MapPermission::R | MapPermission::W) {
+            return Err(LoadError::NoMemory);
+        }
+        let layout = UserLayout{
+            user_stack_bottom,
+            user_stack_top,
+            heap_bottom,
+        };
+        Ok((memory_set, layout, elf.entry))
+    }
+    pub fn activate(&self){
+        let satp = self.page_table.token();
+        unsafe{
+            satp::write(satp);
+            asm!("sfence.vma");
+        }
+    }
+    pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry>{
+        self.page_table.translate(vpn)
+    }
+    // [start_vpn, end_vpn) 中的每一页是否都位于满足 f 的逻辑段中，按逻辑段而不是逐页检查
+    fn covered_by(&self, start_vpn: VirtPageNum, end_vpn: VirtPageNum, f: impl Fn(&MapArea) -> bool) -> bool{
+        let mut vpn = start_vpn;
+        while vpn < end_vpn {
+            match self.areas.iter().find(|area| area.contains(vpn) && f(area)) {
+                Some(area) => vpn = area.vpn_range.get_end(),
+                None => return false,
+            }
+        }
+        true
+    }
+    // 映射 [start, start + len) 的匿名内存，start 按页对齐，与已有的逻辑段重叠时返回false
+    pub fn mmap(&mut self, start: VirtAddr, len: usize, permission: MapPermission) -> bool{
+        let start_vpn = start.floor();
+        let end_vpn = VirtAddr::from(start.0 + len).ceil();
+        if self.areas.iter().any(|area| area.overlaps(start_vpn, end_vpn)) {
+            return false;
+        }
+        let mut area = MapArea::new(start, VirtAddr::from(start.0 + len), MapType::Lazy, permission);
+        area.from_mmap = true;
+        self.push(area, None);
+        true
+    }
+    // 取消 [start, start + len) 的映射，其中每一页都必须位于由 mmap 建立的逻辑段中，
+    // ELF的各个段、用户栈和堆不能被取消映射。逻辑段被部分取消映射时拆分为剩余的部分
+    pub fn munmap(&mut self, start: VirtAddr, len: usize) -> bool{
+        let start_vpn = start.floor();
+        let end_vpn = VirtAddr::from(start.0 + len).ceil();
+        if !self.covered_by(start_vpn, end_vpn, |area| area.from_mmap) {
+            return false;
+        }
+        let mut areas = Vec::new();
+        for mut area in core::mem::take(&mut self.areas){
+            let l = area.vpn_range.get_start();
+            let r = area.vpn_range.get_end();
+            if !area.overlaps(start_vpn, end_vpn) {
+                areas.push(area);
+                continue;
+            }
+            let hole_l = max(l, start_vpn);
+            let hole_r = min(r, end_vpn);
+            for vpn in VPNRange::new(hole_l, hole_r){
+                area.unmap_one(&mut self.page_table, vpn);
+            }
+            if l < hole_l {
+                areas.push(area.take_range(l, hole_l));
+            }
+            if hole_r < r {
+                areas.push(area.take_range(hole_r, r));
+            }
+        }
+        self.areas = areas;
+        self.prune_resident();
+        true
+    }
+    // fork：复制当前地址空间。用户可写的页在父子之间共享并都改为只读的写时复制页，
+    // 其他用户页只读共享，不属于用户的页（TrapContext）直接复制；已换出的页共享交换区中的slot。
+    // 没有足够的空闲页帧建立页表时返回 None，已经改为写时复制的页在父地址空间中仍然可以正常使用
+    pub fn fork(&mut self) -> Option<Self>{
+        let mut memory_set = Self::new_bare()?;
+        if !memory_set.map_trampoline() {
+            return None;
+        }
+        memory_set.resident = self.resident.clone();
+        for area in self.areas.iter(){
+            let mut new_area = MapArea::from_another(area);
+            new_area.swapped = area.swapped.clone();
+            if area.map_perm.contains(MapPermission::U) {
+                let cow = area.map_perm.contains(MapPermission::W);
+                for (vpn, frame) in area.data_frames.iter(){
+                    let pte = self.page_table.translate(*vpn).unwrap();
+                    if !memory_set.page_table.map(*vpn, frame.ppn, pte.flags()) {
+                        return None;
+                    }
+                    if cow {
+                        self.page_table.set_cow(*vpn);
+                        memory_set.page_table.set_cow(*vpn);
+                    }
+                    new_area.data_frames.insert(*vpn, frame.clone());
+                }
+            } else {
+                if !new_area.map(&mut memory_set.page_table) {
+                    return None;
+                }
+                for vpn in area.vpn_range{
+                    let src = self.page_table.translate(vpn).unwrap().ppn();
+                    let dst = memory_set.page_table.translate(vpn).unwrap().ppn();
+                    dst.get_bytes_array().copy_from_slice(src.get_bytes_array());
+                }
+            }
+            memory_set.areas.push(new_area);
+        }
+        Some(memory_set)
+    }
+    // 用户访问 va 时的缺页：为按需分配的逻辑段分配页或从交换区换入，或者在写入时复制写时复制页；
+    // 其他情况（包括没有空闲页帧）返回false，由调用者kill该app
+    pub fn handle_page_fault(&mut self, va: VirtAddr, write: bool) -> bool{
+        let vpn = va.floor();
+        let idx = match self.areas.iter().position(|area| area.contains(vpn)) {
+            Some(idx) => idx,
+            None => return false,
+        };
+        if let Some(pte) = self.page_table.translate(vpn).filter(|pte| pte.is_valid()) {
+            let handled = write && pte.is_cow() && self.areas[idx].copy_on_write(&mut self.page_table, vpn);
+            if handled {
+                self.fault_stats.minor += 1;
+            }
+            return handled;
+        }
+        if self.areas[idx].map_type != MapType::Lazy {
+            return false;
+        }
+        let frame = match self.alloc_resident_frame() {
+            Some(frame) => frame,
+            None => return false,
+        };
+        let area = &mut self.areas[idx];
+        if let Some(slot) = area.swapped.get(&vpn) {
+            if !swap_in(slot, frame.ppn) {
+                return false;
+            }
+        }
+        if !area.map_frame(&mut self.page_table, vpn, frame) {
+            return false;
+        }
+        let major = area.swapped.remove(&vpn).is_some();
+        self.resident.insert(vpn);
+        if major {
+            self.fault_stats.major += 1;
+        } else {
+            self.fault_stats.minor += 1;
+        }
+        true
+    }
+    // 驻留的页达到上限或没有空闲页帧时先换出一页；交换区不可用时驻留的页数不受限制
+    fn alloc_resident_frame(&mut self) -> Option<FrameTracker>{
+        if self.resident.len() >= USER_RESIDENT_PAGES {
+            self.evict_one();
+        }
+        frame_alloc().or_else(|| if self.evict_one() { frame_alloc() } else { None })
+    }
+    // 由置换策略选出一个只属于该地址空间的页并换出
+    fn evict_one(&mut self) -> bool{
+        if !swap_enabled() {
+            return false;
+        }
+        let areas = &self.areas;
+        let victim = self.resident.pick_victim(&mut self.page_table, |vpn| {
+            areas
+                .iter()
+                .find(|area| area.contains(vpn))
+                .and_then(|area| area.data_frames.get(&vpn))
+                .map_or(false, |frame| Arc::strong_count(frame) == 1)
+        });
+        let vpn = match victim {
+            Some(vpn) => vpn,
+            None => return false,
+        };
+        let area = self.areas.iter_mut().find(|area| area.contains(vpn)).unwrap();
+        if area.swap_out(&mut self.page_table, vpn) {
+            true
+        } else {
+            self.resident.insert(vpn);
+            false
+        }
+    }
+    // 取消映射之后，从置换策略中移除已经不再驻留的页
+    fn prune_resident(&mut self){
+        let areas = &self.areas;
+        self.resident.retain(|vpn| areas.iter().any(|area| area.data_frames.contains_key(&vpn)));
+    }
+    pub fn fault_stats(&self) -> FaultStats{
+        self.fault_stats
+    }
+    // [start, start + len) 是否整个位于用户可读（write 为true时可写）的逻辑段中，其中的页可能还没有分配或已换出
+    pub fn user_range_accessible(&self, start: usize, len: usize, write: bool) -> bool{
+        let end = match start.checked_add(len) {
+            Some(end) if end <= USER_SPACE_END => end,
+            _ => return false,
+        };
+        let needed = MapPermission::U | if write { MapPermission::W } else { MapPermission::R };
+        len == 0 || self.covered_by(VirtAddr::from(start).floor(), VirtAddr::from(end).ceil(), |area| {
+            area.map_perm.contains(needed)
+        })
+    }
+    // 内核直接访问 va 所在的页之前，先像用户访问时那样处理缺页，返回之后该页是否可以访问
+    pub fn populate_page(&mut self, va: usize, write: bool) -> bool{
+        let va = VirtAddr::from(va);
+        match self.page_table.translate(va.floor()).filter(|pte| pte.is_valid()) {
+            Some(pte) if !write || pte.writable() => true,
+            _ => self.handle_page_fault(va, write),
+        }
+    }
+    // 依次处理 [start, start + len) 中每一页的缺页，遇到第一个无法访问的页时返回false。
+    // 处理后面的页时前面的页可能被换出，len 应当远小于 USER_RESIDENT_PAGES 页
+    pub fn populate(&mut self, start: usize, len: usize, write: bool) -> bool{
+        let end = match start.checked_add(len) {
+            Some(end) => end,
+            None => return false,
+        };
+        let mut va = start;
+        while va < end {
+            if !self.populate_page(va, write) {
+                return false;
+            }
+            va = (va / PAGE_SIZE + 1) * PAGE_SIZE;
+        }
+        true
+    }
+    // 把起始于 start 的逻辑段的末尾缩小到 new_end，之后不再需要的页被回收
+    pub fn shrink_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool{
+        let start_vpn = start.floor();
+        let new_end_vpn = new_end.ceil();
+        match self.areas.iter_mut().find(|area| area.vpn_range.get_start() == start_vpn) {
+            Some(area) if start_vpn <= new_end_vpn && new_end_vpn <= area.vpn_range.get_end() => {
+                area.shrink_to(&mut self.page_table, new_end_vpn);
+                self.prune_resident();
+                true
+            },
+            _ => false,
+        }
+    }
+    // 把起始于 start 的逻辑段的末尾扩展到 new_end，新增的页不能与其他逻辑段重叠；
+    // 没有足够的空闲页帧时逻辑段保持不变
+    pub fn append_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool{
+        let start_vpn = start.floor();
+        let new_end_vpn = new_end.ceil();
+        let idx = match self.areas.iter().position(|area| area.vpn_range.get_start() == start_vpn) {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let end_vpn = self.areas[idx].vpn_range.get_end();
+        if new_end_vpn < end_vpn {
+            return false;
+        }
+        if self.areas.iter().enumerate().any(|(i, area)| i != idx && area.overlaps(end_vpn, new_end_vpn)) {
+            return false;
+        }
+        self.areas[idx].append_to(&mut self.page_table, new_end_vpn)
+    }
+    // app退出时回收它的所有数据页，页表本身随 MemorySet 一起回收
+    pub fn recycle_data_pages(&mut self){
+        self.areas.clear();
+        self.resident = ActiveReplacer::new();
+    }
+}
+
+pub struct MapArea{
+    vpn_range: VPNRange,
+    // fork后多个地址空间可以共享同一个页帧，最后一个引用被drop时页帧才被回收
+    data_frames: BTreeMap<VirtPageNum, Arc<FrameTracker>>,
+    // 已换出的页，同样可以在fork后被共享
+    swapped: BTreeMap<VirtPageNum, Arc<SwapSlot>>,
+    map_type: MapType,
+    map_perm: MapPermission,
+    // 由 sys_mmap 建立，只有这样的逻辑段可以被 munmap
+    from_mmap: bool,
+}
+
+impl MapArea{
+    pub fn new(start_va: VirtAddr, end_va: VirtAddr, map_type: MapType, map_perm: MapPermission) -> Self{
+        let start_vpn: VirtPageNum = start_va.floor();
+        let end_vpn: VirtPageNum = end_va.ceil();
+        Self{
+            vpn_range: VPNRange::new(start_vpn, end_vpn),
+            data_frames: BTreeMap::new(),
+            swapped: BTreeMap::new(),
+            map_type,
+            map_perm,
+            from_mmap: false,
+        }
+    }
+    // 与 another 的范围和权限相同，但还没有映射任何页
+    fn from_another(another: &Self) -> Self{
+        Self{
+            vpn_range: another.vpn_range,
+            data_frames: BTreeMap::new(),
+            swapped: BTreeMap::new(),
+            map_type: another.map_type,
+            map_perm: another.map_perm,
+            from_mmap: another.from_mmap,
+        }
+    }
+    // 没有空闲页帧时返回false，该页保持未映射
+    fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool{
+        match self.map_type {
+            MapType::Identical => {
+                let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+                page_table.map(vpn, PhysPageNum(vpn.0), pte_flags)
+            },
+            MapType::Framed | MapType::Lazy => match frame_alloc() {
+                Some(frame) => self.map_frame(page_table, vpn, frame),
+                None => false,
+            },
+        }
+    }
+    // 按需分配的页在缺页时才映射到 frame，没有空闲页帧存放中间页表时返回false
+    fn map_frame(&mut self, page_table: &mut PageTable, vpn: VirtPageNum, frame: FrameTracker) -> bool{
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        if !page_table.map(vpn, frame.ppn, pte_flags) {
+            return false;
+        }
+        self.data_frames.insert(vpn, Arc::new(frame));
+        true
+    }
+    // 映射 [start, end) 中的每一页，失败时取消其中已经映射的页
+    fn map_range(&mut self, page_table: &mut PageTable, start: VirtPageNum, end: VirtPageNum) -> bool{
+        for vpn in VPNRange::new(start, end){
+            if !self.map_one(page_table, vpn) {
+                for mapped in VPNRange::new(start, vpn){
+                    self.unmap_one(page_table, mapped);
+                }
+                return false;
+            }
+        }
+        true
+    }
+    // 把页写入交换区并取消映射，交换区已满时返回false
+    fn swap_out(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool{
+        let frame = self.data_frames.remove(&vpn).unwrap();
+        match swap_out(frame.ppn) {
+            Some(slot) => {
+                page_table.unmap(vpn);
+                self.swapped.insert(vpn, Arc::new(slot));
+                true
+            },
+            None => {
+                self.data_frames.insert(vpn, frame);
+                false
+            },
+        }
+    }
+    fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum){
+        match self.map_type {
+            MapType::Identical => {},
+            MapType::Framed => {
+                self.data_frames.remove(&vpn);
+            },
+            // 还没有被访问过或已换出的页没有映射
+            MapType::Lazy => {
+                if self.data_frames.remove(&vpn).is_none() {
+                    self.swapped.remove(&vpn);
+                    return;
+                }
+            },
+        }
+        page_table.unmap(vpn);
+    }
+    fn map(&mut self, page_table: &mut PageTable) -> bool{
+        if self.map_type == MapType::Lazy {
+            return true;
+        }
+        self.map_range(page_table, self.vpn_range.get_start(), self.vpn_range.get_end())
+    }
+    fn shrink_to(&mut self, page_table: &mut PageTable, new_end: VirtPageNum){
+        for vpn in VPNRange::new(new_end, self.vpn_range.get_end()){
+            self.unmap_one(page_table, vpn);
+        }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+    }
+    fn append_to(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) -> bool{
+        if self.map_type != MapType::Lazy && !self.map_range(page_table, self.vpn_range.get_end(), new_end) {
+            return false;
+        }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+        true
+    }
+    fn copy_on_write(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool{
+        let frame = self.data_frames.get(&vpn).unwrap();
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        // 其他地址空间都已不再使用该页帧，不需要复制
+        if Arc::strong_count(frame) == 1 {
+            page_table.remap(vpn, frame.ppn, pte_flags);
+            return true;
+        }
+        let new_frame = match frame_alloc() {
+            Some(new_frame) => new_frame,
+            None => return false,
+        };
+        new_frame.ppn.get_bytes_array().copy_from_slice(frame.ppn.get_bytes_array());
+        page_table.remap(vpn, new_frame.ppn, pte_flags);
+        self.data_frames.insert(vpn, Arc::new(new_frame));
+        true
+    }
+    fn contains(&self, vpn: VirtPageNum) -> bool{
+        self.vpn_range.get_start() <= vpn && vpn < self.vpn_range.get_end()
+    }
+    fn overlaps(&self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool{
+        self.vpn_range.get_start() < end_vpn && start_vpn < self.vpn_range.get_end()
+    }
+    // 把 [start_vpn, end_vpn) 中已映射的页移到一个新的逻辑段中，映射本身不变
+    fn take_range(&mut self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> Self{
+        let mut data_frames = self.data_frames.split_off(&start_vpn);
+        let mut rest = data_frames.split_off(&end_vpn);
+        self.data_frames.append(&mut rest);
+        let mut swapped = self.swapped.split_off(&start_vpn);
+        let mut rest = swapped.split_off(&end_vpn);
+        self.swapped.append(&mut rest);
+        Self{
+            vpn_range: VPNRange::new(start_vpn, end_vpn),
+            data_frames,
+            swapped,
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+            from_mmap: self.from_mmap,
+        }
+    }
+    // data 从逻辑段第一页的 offset 处开始
+    fn copy_data(&mut self, page_table: &mut PageTable, offset: usize, data: &[u8]){
+        assert_eq!(self.map_type, MapType::Framed);
+        let mut current_vpn = self.vpn_range.get_start();
+        let mut page_offset = offset;
+        let mut data = data;
+        while !data.is_empty() {
+            let len = data.len().min(PAGE_SIZE - page_offset);
+            let dst = &mut page_table
+                .translate(current_vpn)
+                .unwrap()
+                .ppn()
+                .get_bytes_array()[page_offset..page_offset + len];
+            dst.copy_from_slice(&data[..len]);
+            data = &data[len..];
+            page_offset = 0;
+            current_vpn.step();
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MapType{
+    Identical,
+    Framed,
+    // 与Framed相同，但每一页在第一次被访问时才分配
+    Lazy,
+}
+
+bitflags!{
+    // 与 PTEFlags 中对应的位相同
+    pub struct MapPermission: u8 {
+        const R = 1 << 1;
+        const W = 1 << 2;
+        const X = 1 << 3;
+        const U = 1 << 4;
+    }
+}
+
+// 检查内核各段的权限是否正确
+pub fn remap_test(){
+    let kernel_space = KERNEL_SPACE.exclusive_access();
+    let mid_text: VirtAddr = ((stext as usize + etext as usize) / 2).into();
+    let mid_rodata: VirtAddr = ((srodata as usize + erodata as usize) / 2).into();
+    let mid_data: VirtAddr = ((sdata as usize + edata as usize) / 2).into();
+    assert!(!kernel_space.page_table.translate(mid_text.floor()).unwrap().writable());
+    assert!(!kernel_space.page_table.translate(mid_rodata.floor()).unwrap().writable());
+    assert!(!kernel_space.page_table.translate(mid_data.floor()).unwrap().executable());
+    println!("[kernel] remap_test passed!");
+}
@@ -0,0 +1,22 @@
+// 内存管理：内核堆、物理页帧分配、SV39地址空间和交换区
+
+mod address;
+mod frame_allocator;
+mod heap_allocator;
+mod memory_set;
+mod page_table;
+mod replace;
+mod swap;
+
+pub use address::{PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum, VPNRange};
+pub use frame_allocator::{frame_alloc, frame_allocator_test, frame_stats, print_frame_stats, FrameStats, FrameTracker};
+pub use memory_set::{remap_test, FaultStats, LoadError, MapPermission, MemorySet, UserLayout, KERNEL_SPACE};
+pub use page_table::{translated_byte_buffer, PTEFlags, PageTable, PageTableEntry};
+pub use swap::{init_swap, print_swap_stats, swap_stats, SwapStats};
+
+// 初始化堆和页帧分配器，然后开启分页，切换到内核地址空间
+pub fn init(){
+    heap_allocator::init_heap();
+    frame_allocator::init_frame_allocator();
+    KERNEL_SPACE.exclusive_access().activate();
+}
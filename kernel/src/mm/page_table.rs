@@ -0,0 +1,195 @@
+// SV39 三级页表
+
+use super::{frame_alloc, FrameTracker, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+use crate::config::USER_SPACE_END;
+use alloc::vec;
+use alloc::vec::Vec;
+
+bitflags!{
+    pub struct PTEFlags: u8 {
+        const V = 1 << 0;
+        const R = 1 << 1;
+        const W = 1 << 2;
+        const X = 1 << 3;
+        const U = 1 << 4;
+        const G = 1 << 5;
+        const A = 1 << 6;
+        const D = 1 << 7;
+    }
+}
+
+// 页表项中由软件使用的 RSW 位，标记fork后共享的写时复制页，这样的页映射为只读
+const PTE_COW: usize = 1 << 8;
+
+// [53:10] 为物理页号，[9:8] 为RSW，[7:0] 为标志位
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct PageTableEntry{
+    pub bits: usize,
+}
+
+impl PageTableEntry{
+    pub fn new(ppn: PhysPageNum, flags: PTEFlags) -> Self{
+        PageTableEntry{
+            bits: ppn.0 << 10 | flags.bits as usize,
+        }
+    }
+    pub fn empty() -> Self{
+        PageTableEntry{ bits: 0 }
+    }
+    pub fn ppn(&self) -> PhysPageNum{
+        (self.bits >> 10 & ((1usize << 44) - 1)).into()
+    }
+    pub fn flags(&self) -> PTEFlags{
+        PTEFlags::from_bits(self.bits as u8).unwrap()
+    }
+    pub fn is_valid(&self) -> bool{
+        (self.flags() & PTEFlags::V) != PTEFlags::empty()
+    }
+    pub fn readable(&self) -> bool{
+        (self.flags() & PTEFlags::R) != PTEFlags::empty()
+    }
+    pub fn writable(&self) -> bool{
+        (self.flags() & PTEFlags::W) != PTEFlags::empty()
+    }
+    pub fn executable(&self) -> bool{
+        (self.flags() & PTEFlags::X) != PTEFlags::empty()
+    }
+    pub fn is_user(&self) -> bool{
+        (self.flags() & PTEFlags::U) != PTEFlags::empty()
+    }
+    pub fn is_cow(&self) -> bool{
+        self.bits & PTE_COW != 0
+    }
+}
+
+// frames 保存根页表和所有中间页表所在的页帧，随页表一起回收
+pub struct PageTable{
+    root_ppn: PhysPageNum,
+    frames: Vec<FrameTracker>,
+}
+
+impl PageTable{
+    // 没有空闲页帧存放根页表时返回 None
+    pub fn new() -> Option<Self>{
+        let frame = frame_alloc()?;
+        Some(PageTable{
+            root_ppn: frame.ppn,
+            frames: vec![frame],
+        })
+    }
+    // 只用于查询其他地址空间（如当前app）的映射，不拥有任何页帧
+    pub fn from_token(satp: usize) -> Self{
+        Self{
+            root_ppn: PhysPageNum::from(satp & ((1usize << 44) - 1)),
+            frames: Vec::new(),
+        }
+    }
+    fn find_pte_create(&mut self, vpn: VirtPageNum) -> Option<&mut PageTableEntry>{
+        let idxs = vpn.indexes();
+        let mut ppn = self.root_ppn;
+        let mut result: Option<&mut PageTableEntry> = None;
+        for (i, idx) in idxs.iter().enumerate(){
+            let pte = &mut ppn.get_pte_array()[*idx];
+            if i == 2 {
+                result = Some(pte);
+                break;
+            }
+            if !pte.is_valid() {
+                let frame = frame_alloc()?;
+                *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
+                self.frames.push(frame);
+            }
+            ppn = pte.ppn();
+        }
+        result
+    }
+    fn find_pte(&self, vpn: VirtPageNum) -> Option<&mut PageTableEntry>{
+        let idxs = vpn.indexes();
+        let mut ppn = self.root_ppn;
+        let mut result: Option<&mut PageTableEntry> = None;
+        for (i, idx) in idxs.iter().enumerate(){
+            let pte = &mut ppn.get_pte_array()[*idx];
+            if i == 2 {
+                result = Some(pte);
+                break;
+            }
+            if !pte.is_valid() {
+                return None;
+            }
+            ppn = pte.ppn();
+        }
+        result
+    }
+    // 没有空闲页帧存放中间页表时返回false
+    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) -> bool{
+        let pte = match self.find_pte_create(vpn) {
+            Some(pte) => pte,
+            None => return false,
+        };
+        assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+        true
+    }
+    pub fn unmap(&mut self, vpn: VirtPageNum){
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
+        *pte = PageTableEntry::empty();
+    }
+    // 把已映射的页改为只读的写时复制页
+    pub fn set_cow(&mut self, vpn: VirtPageNum){
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before setting cow", vpn);
+        pte.bits = (pte.bits & !(PTEFlags::W.bits as usize)) | PTE_COW;
+    }
+    // 写时复制完成，把页重新映射到 ppn（可能与原来相同），并清除COW标记
+    pub fn remap(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags){
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before remapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+    }
+    // 返回页是否在上次调用之后被访问过（A位由硬件置位），并清除A位
+    pub fn take_accessed(&mut self, vpn: VirtPageNum) -> bool{
+        match self.find_pte(vpn) {
+            Some(pte) if pte.is_valid() && pte.flags().contains(PTEFlags::A) => {
+                pte.bits &= !(PTEFlags::A.bits as usize);
+                true
+            },
+            _ => false,
+        }
+    }
+    pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry>{
+        self.find_pte(vpn).map(|pte| *pte)
+    }
+    // satp: MODE = 8 (SV39) | 根页表的物理页号
+    pub fn token(&self) -> usize{
+        8usize << 60 | self.root_ppn.0
+    }
+}
+
+// 把用户地址空间中 [ptr, ptr + len) 的缓冲区转换为内核可以直接访问的若干段物理内存，
+// 其中任何一页没有映射、不允许用户访问或者 write 为true时不可写，都返回 None
+pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize, write: bool) -> Option<Vec<&'static mut [u8]>>{
+    let page_table = PageTable::from_token(token);
+    let mut start = ptr as usize;
+    // 更高的地址在截断为39位后会与低地址重叠
+    let end = start.checked_add(len).filter(|end| *end <= USER_SPACE_END)?;
+    let mut v = Vec::new();
+    while start < end {
+        let start_va = VirtAddr::from(start);
+        let mut vpn = start_va.floor();
+        let pte = page_table
+            .translate(vpn)
+            .filter(|pte| pte.is_valid() && pte.is_user() && (!write || pte.writable()))?;
+        vpn.step();
+        let end_va = VirtAddr::from(end).min(VirtAddr::from(vpn));
+        let ppn = pte.ppn();
+        if end_va.page_offset() == 0 {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..]);
+        } else {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..end_va.page_offset()]);
+        }
+        start = end_va.into();
+    }
+    Some(v)
+}
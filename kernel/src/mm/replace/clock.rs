@@ -0,0 +1,39 @@
+use super::{PageTable, Replacer, VirtPageNum};
+use alloc::collections::VecDeque;
+
+// 时钟（二次机会）：队首的页被访问过（A位为1）时清除A位并移到队尾，否则换出
+#[derive(Clone)]
+pub struct ClockReplacer{
+    queue: VecDeque<VirtPageNum>,
+}
+
+impl Replacer for ClockReplacer{
+    fn new() -> Self{
+        Self{
+            queue: VecDeque::new(),
+        }
+    }
+    fn name(&self) -> &'static str{
+        "clock"
+    }
+    fn len(&self) -> usize{
+        self.queue.len()
+    }
+    fn insert(&mut self, vpn: VirtPageNum){
+        self.queue.push_back(vpn);
+    }
+    fn retain<F: FnMut(VirtPageNum) -> bool>(&mut self, mut f: F){
+        self.queue.retain(|vpn| f(*vpn));
+    }
+    fn pick_victim<F: Fn(VirtPageNum) -> bool>(&mut self, page_table: &mut PageTable, evictable: F) -> Option<VirtPageNum>{
+        // 第一轮清除了所有A位，第二轮一定能找到可换出的页（如果有的话）
+        for _ in 0..2 * self.queue.len(){
+            let vpn = self.queue.pop_front().unwrap();
+            if evictable(vpn) && !page_table.take_accessed(vpn) {
+                return Some(vpn);
+            }
+            self.queue.push_back(vpn);
+        }
+        None
+    }
+}
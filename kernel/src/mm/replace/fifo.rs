@@ -0,0 +1,32 @@
+use super::{PageTable, Replacer, VirtPageNum};
+use alloc::collections::VecDeque;
+
+// 先进先出：换出最早被映射的页
+#[derive(Clone)]
+pub struct FifoReplacer{
+    queue: VecDeque<VirtPageNum>,
+}
+
+impl Replacer for FifoReplacer{
+    fn new() -> Self{
+        Self{
+            queue: VecDeque::new(),
+        }
+    }
+    fn name(&self) -> &'static str{
+        "fifo"
+    }
+    fn len(&self) -> usize{
+        self.queue.len()
+    }
+    fn insert(&mut self, vpn: VirtPageNum){
+        self.queue.push_back(vpn);
+    }
+    fn retain<F: FnMut(VirtPageNum) -> bool>(&mut self, mut f: F){
+        self.queue.retain(|vpn| f(*vpn));
+    }
+    fn pick_victim<F: Fn(VirtPageNum) -> bool>(&mut self, _page_table: &mut PageTable, evictable: F) -> Option<VirtPageNum>{
+        let idx = self.queue.iter().position(|vpn| evictable(*vpn))?;
+        self.queue.remove(idx)
+    }
+}
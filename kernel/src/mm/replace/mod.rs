@@ -0,0 +1,31 @@
+// 可替换的页面置换策略，通过cargo feature选择：swap_fifo / swap_clock
+
+mod clock;
+mod fifo;
+
+use super::{PageTable, VirtPageNum};
+
+pub use clock::ClockReplacer;
+pub use fifo::FifoReplacer;
+
+#[cfg(all(feature = "swap_fifo", feature = "swap_clock"))]
+compile_error!("select at most one of the swap_fifo / swap_clock features");
+
+#[cfg(feature = "swap_fifo")]
+pub type ActiveReplacer = FifoReplacer;
+// clock when no policy is selected
+#[cfg(not(feature = "swap_fifo"))]
+pub type ActiveReplacer = ClockReplacer;
+
+// 置换策略只管理一个地址空间中驻留的可换出页，页本身的映射和换入换出由 MemorySet 完成
+pub trait Replacer: Clone{
+    fn new() -> Self where Self: Sized;
+    fn name(&self) -> &'static str;
+    fn len(&self) -> usize;
+    // 页被映射（按需分配或换入）
+    fn insert(&mut self, vpn: VirtPageNum);
+    // 只保留 f 返回true的页，用于取消映射之后
+    fn retain<F: FnMut(VirtPageNum) -> bool>(&mut self, f: F);
+    // 选出一个要换出的页并把它移除，evictable 为false的页（如仍被共享的页）不能被换出
+    fn pick_victim<F: Fn(VirtPageNum) -> bool>(&mut self, page_table: &mut PageTable, evictable: F) -> Option<VirtPageNum>;
+}
@@ -0,0 +1,129 @@
+// 交换区：块设备被划分为以页为单位的slot，被换出的用户页保存在其中
+
+use super::replace::{ActiveReplacer, Replacer};
+use super::PhysPageNum;
+use crate::config::PAGE_SIZE;
+use crate::drivers::{BLOCK_DEVICE, BLOCK_SIZE};
+use crate::sync::UPSafeCell;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Formatter};
+
+const BLOCKS_PER_SLOT: usize = PAGE_SIZE / BLOCK_SIZE;
+
+// 交换区中一个slot的所有权，drop时自动回收
+pub struct SwapSlot{
+    id: usize,
+}
+
+impl Debug for SwapSlot{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result{
+        f.write_fmt(format_args!("SwapSlot:{}", self.id))
+    }
+}
+
+impl Drop for SwapSlot{
+    fn drop(&mut self){
+        SWAP_SPACE.exclusive_access().dealloc(self.id);
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SwapStats{
+    pub total: usize,
+    pub used: usize,
+    pub swap_outs: usize,
+    pub swap_ins: usize,
+}
+
+struct SwapSpace{
+    used: Vec<bool>,
+    stats: SwapStats,
+}
+
+impl SwapSpace{
+    fn new() -> Self{
+        let total = BLOCK_DEVICE
+            .as_ref()
+            .map_or(0, |blk| blk.exclusive_access().capacity() / BLOCKS_PER_SLOT);
+        Self{
+            used: vec![false; total],
+            stats: SwapStats{
+                total,
+                ..Default::default()
+            },
+        }
+    }
+    fn alloc(&mut self) -> Option<usize>{
+        let id = self.used.iter().position(|used| !*used)?;
+        self.used[id] = true;
+        self.stats.used += 1;
+        Some(id)
+    }
+    fn dealloc(&mut self, id: usize){
+        assert!(self.used[id], "swap slot {} has not been allocated!", id);
+        self.used[id] = false;
+        self.stats.used -= 1;
+    }
+}
+
+lazy_static!{
+    static ref SWAP_SPACE: UPSafeCell<SwapSpace> = unsafe{ UPSafeCell::new(SwapSpace::new()) };
+}
+
+pub fn init_swap(){
+    let total = SWAP_SPACE.exclusive_access().stats.total;
+    if total == 0 {
+        println!("[kernel] swap: no block device, page replacement is disabled");
+    } else {
+        println!(
+            "[kernel] swap: {} slots on virtio block device, replacement policy: {}",
+            total, ActiveReplacer::new().name()
+        );
+    }
+}
+
+pub fn swap_enabled() -> bool{
+    SWAP_SPACE.exclusive_access().stats.total != 0
+}
+
+// 把物理页 ppn 的内容写入一个新的slot，交换区已满或写入失败时返回 None
+pub fn swap_out(ppn: PhysPageNum) -> Option<SwapSlot>{
+    let id = SWAP_SPACE.exclusive_access().alloc()?;
+    let slot = SwapSlot{ id };
+    let written = BLOCK_DEVICE
+        .as_ref()
+        .unwrap()
+        .exclusive_access()
+        .write_blocks(id * BLOCKS_PER_SLOT, ppn.get_bytes_array());
+    if !written {
+        return None;
+    }
+    SWAP_SPACE.exclusive_access().stats.swap_outs += 1;
+    Some(slot)
+}
+
+// 把 slot 的内容读入物理页 ppn，slot 本身仍由调用者持有
+pub fn swap_in(slot: &SwapSlot, ppn: PhysPageNum) -> bool{
+    let read = BLOCK_DEVICE
+        .as_ref()
+        .unwrap()
+        .exclusive_access()
+        .read_blocks(slot.id * BLOCKS_PER_SLOT, ppn.get_bytes_array());
+    if read {
+        SWAP_SPACE.exclusive_access().stats.swap_ins += 1;
+    }
+    read
+}
+
+pub fn swap_stats() -> SwapStats{
+    SWAP_SPACE.exclusive_access().stats
+}
+
+pub fn print_swap_stats(){
+    let stats = swap_stats();
+    println!(
+        "[kernel] swap: total={} used={} swap_outs={} swap_ins={}",
+        stats.total, stats.used, stats.swap_outs, stats.swap_ins
+    );
+}
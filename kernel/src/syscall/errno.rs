@@ -0,0 +1,51 @@
+// 系统调用的错误码，编号与Linux相同，系统调用失败时返回错误码的相反数。
+// 内核和 apps_lib 共用这个文件（apps_lib 通过 #[path] 引用），因此其中的项不一定两边都用到
+#![allow(dead_code)]
+
+// 沿用 errno 的习惯命名
+#[allow(clippy::upper_case_acronyms)]
+#[repr(isize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Errno{
+    ENOENT = 2,
+    EBADF = 9,
+    ENOMEM = 12,
+    EFAULT = 14,
+    EBUSY = 16,
+    EEXIST = 17,
+    EINVAL = 22,
+    ENOSYS = 38,
+}
+
+const ALL_ERRNOS: [Errno; 8] = [
+    Errno::ENOENT,
+    Errno::EBADF,
+    Errno::ENOMEM,
+    Errno::EFAULT,
+    Errno::EBUSY,
+    Errno::EEXIST,
+    Errno::EINVAL,
+    Errno::ENOSYS,
+];
+
+// 成功时为非负的返回值
+pub type SysResult = Result<usize, Errno>;
+
+// 内核：把系统调用的结果编码为 a0 中的返回值
+pub fn encode(result: SysResult) -> isize{
+    match result {
+        Ok(value) => value as isize,
+        Err(errno) => -(errno as isize),
+    }
+}
+
+// apps_lib：把 a0 中的返回值解码为系统调用的结果
+pub fn decode(ret: isize) -> SysResult{
+    if ret >= 0 {
+        return Ok(ret as usize);
+    }
+    match ALL_ERRNOS.iter().find(|errno| **errno as isize == -ret) {
+        Some(errno) => Err(*errno),
+        None => panic!("unknown errno {}", -ret),
+    }
+}
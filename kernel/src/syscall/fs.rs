@@ -1,28 +1,44 @@
 const FD_STDOUT: usize = 1;
 
-use crate::batch::USER_STACK;
-const USER_STACK_SIZE: usize = 4096;
-const APP_SIZE_LIMIT: usize = 0x20000;
-const APP_BASE_ADDRESS: usize = 0x80400000;
+use super::errno::{Errno, SysResult};
+use super::uaccess::UserBuffer;
+use crate::console::{print_bytes, print_bytes_lossy};
 
-pub fn sys_write(fd: usize,buf: *const u8,len: usize) -> isize{
+#[cfg(all(feature = "console_raw", feature = "console_lossy"))]
+compile_error!("select at most one of the console_raw / console_lossy features");
+
+// 只支持标准输出，其他 fd 返回 EBADF。
+// 缓冲区必须完整地位于当前app可读的内存中，否则返回 EFAULT；
+// 输出途中某一页无法换入时只输出之前的部分，返回已输出的字节数
+pub fn sys_write(fd: usize,buf: *const u8,len: usize) -> SysResult{
     match fd {
         FD_STDOUT => {
-            // unsafe {println!("#{:#x} {:#x} #", buf as usize , USER_STACK.get_sp() - USER_STACK_SIZE);}
-            // 打印数据在用户栈上或者在数据段内
-            if (((buf as usize)  >= USER_STACK.get_sp() - USER_STACK_SIZE) && ((buf as usize) + len <= USER_STACK.get_sp())) 
-            || (((buf as usize) + len <= APP_SIZE_LIMIT + APP_BASE_ADDRESS) && ((buf as usize) >= APP_BASE_ADDRESS)){
-                let slice = unsafe { core::slice::from_raw_parts(buf, len) };
-                let str = core::str::from_utf8(slice).unwrap();
-                print!("{}", str);
-                len as isize
-            }else{
-                -1 as isize
+            let buffer = UserBuffer::new(buf as usize, len, false)?;
+            let mut written = 0;
+            let mut error = None;
+            let parts = buffer.pages().map_while(|page| match page {
+                Ok(page) => {
+                    written += page.len();
+                    Some(&*page)
+                },
+                Err(errno) => {
+                    error = Some(errno);
+                    None
+                },
+            });
+            // 默认原样输出字节，console_lossy 时把无效的UTF-8显示为U+FFFD
+            if cfg!(feature = "console_lossy") {
+                print_bytes_lossy(parts);
+            } else {
+                parts.for_each(print_bytes);
+            }
+            match error {
+                Some(errno) if written == 0 => Err(errno),
+                _ => Ok(written),
             }
-
         },
         _ =>{
-            -1 as isize
+            Err(Errno::EBADF)
         }
     }
-}
\ No newline at end of file
+}
@@ -1,15 +1,39 @@
 use self::{fs::sys_write, process::*};
+use self::errno::{encode, Errno, SysResult};
+use crate::batch::note_unknown_syscall;
 
 const SYSCALL_WRITE: usize = 64;
 const SYSCALL_EXIT: usize = 93;
 const SYSCALL_YELD: usize = 124;
+const SYSCALL_SET_PRIORITY: usize = 140;
 const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_SBRK: usize = 214;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_SPAWN: usize = 400;
 
 
+mod errno;
 mod fs;
 mod process;
+#[cfg(feature = "trace")]
+mod trace;
+mod uaccess;
 
-pub fn syscall(syscall_id: usize,args: [usize;3]) -> isize{
+// args 为 a0-a5 中的6个参数，与Linux相同；返回值为非负的结果或错误码的相反数，见 errno.rs
+pub fn syscall(syscall_id: usize,args: [usize;6]) -> isize{
+    #[cfg(feature = "trace")]
+    let entry = trace::enter(syscall_id, &args);
+    let result = dispatch(syscall_id, args);
+    #[cfg(feature = "trace")]
+    if let Some(entry) = entry {
+        trace::exit(syscall_id, entry, &result);
+    }
+    encode(result)
+}
+
+fn dispatch(syscall_id: usize,args: [usize;6]) -> SysResult{
     match syscall_id {
         SYSCALL_EXIT => {
             sys_exit(args[0] as i32)
@@ -20,11 +44,31 @@ pub fn syscall(syscall_id: usize,args: [usize;3]) -> isize{
         SYSCALL_YELD => {
             sys_yield()
         },
+        SYSCALL_SET_PRIORITY => {
+            sys_set_priority(args[0] as isize)
+        },
         SYSCALL_GET_TIME => {
             sys_get_time(args[0] as *mut TimeVal, args[1])
         },
+        SYSCALL_SBRK => {
+            sys_sbrk(args[0] as isize)
+        },
+        SYSCALL_MUNMAP => {
+            sys_munmap(args[0], args[1])
+        },
+        SYSCALL_FORK => {
+            sys_fork()
+        },
+        SYSCALL_MMAP => {
+            sys_mmap(args[0], args[1], args[2])
+        },
+        SYSCALL_SPAWN => {
+            sys_spawn(args[0] as *const u8)
+        },
         _ => {
-            panic!("Unsupported syscall_id: {}", syscall_id)
+            // 不能因为app使用了未知的系统调用而让内核panic
+            note_unknown_syscall(syscall_id);
+            Err(Errno::ENOSYS)
         }
     }
-}
\ No newline at end of file
+}
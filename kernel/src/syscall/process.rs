@@ -1,31 +1,103 @@
-use crate::batch::run_next_app;
+use crate::batch::{exit_current_app, find_app};
+use crate::config::{MAX_PRIORITY, MIN_PRIORITY, PAGE_SIZE, USER_SPACE_END};
+use crate::mm::MapPermission;
+use crate::task::{
+    change_program_brk, fork_current, mmap_current, mmap_reserved, munmap_current, set_current_priority, spawn_task,
+    suspend_current_and_run_next,
+};
+use super::errno::{Errno, SysResult};
+use super::uaccess::{read_user_str, UserPtr};
 use crate::timer::get_time_us;
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct TimeVal{
     pub sec: usize,
     pub usec: usize,
 }
 
-pub fn sys_exit(exit_code: i32) -> isize{
+pub fn sys_exit(exit_code: i32) -> !{
     println!("[kernel] Application exited with code {}", exit_code);
     // this is in batch system we can do
-    run_next_app()
+    exit_current_app(exit_code)
 }
 
-pub fn sys_yield() -> !{
-    loop {}
+pub fn sys_yield() -> SysResult{
+    suspend_current_and_run_next();
+    Ok(0)
 }
 
-pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize{
+// ts 可能跨越两个页，不可写时返回 EFAULT
+pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> SysResult{
     let us = get_time_us();
+    let time_val = TimeVal{
+        sec: us/1_000_000,
+        usec: us%1_000_000,
+    };
+    UserPtr::new(ts as usize).write(time_val)?;
+    Ok(0)
+}
+
+// priority must be in [MIN_PRIORITY, MAX_PRIORITY], returns the new priority or EINVAL
+pub fn sys_set_priority(prio: isize) -> SysResult{
+    if prio < MIN_PRIORITY as isize || prio > MAX_PRIORITY as isize {
+        return Err(Errno::EINVAL);
+    }
+    set_current_priority(prio as usize);
+    Ok(prio as usize)
+}
+
+pub(super) const MAX_APP_NAME_LEN: usize = 64;
+
+// 按名字启动一个已结束的app，返回app编号；名字不存在时返回 ENOENT，app仍在运行或无法加载时返回 EBUSY
+pub fn sys_spawn(name: *const u8) -> SysResult{
+    let name = read_user_str(name as usize, MAX_APP_NAME_LEN)?;
+    let app_id = find_app(&name).ok_or(Errno::ENOENT)?;
+    if spawn_task(app_id) {
+        Ok(app_id)
+    } else {
+        Err(Errno::EBUSY)
+    }
+}
+
+// [start, start + len) 必须按页对齐地开始，长度不为0，并且位于用户地址空间中
+fn user_range_valid(start: usize, len: usize) -> bool{
+    start % PAGE_SIZE == 0 && len != 0 && start.checked_add(len).map_or(false, |end| end <= USER_SPACE_END)
+}
 
-    unsafe{
-        *ts = TimeVal{
-            sec: us/1_000_000,
-            usec: us%1_000_000,
-        }
+// prot: bit0 = R, bit1 = W, bit2 = X，其余位必须为0且不能全为0；
+// 参数无效或覆盖第0页、用户栈的guard page、堆可以增长到的区域时返回 EINVAL，
+// 与已有的映射重叠时返回 EEXIST
+pub fn sys_mmap(start: usize, len: usize, prot: usize) -> SysResult{
+    if !user_range_valid(start, len) || prot & !0x7 != 0 || prot & 0x7 == 0 || mmap_reserved(start, len) {
+        return Err(Errno::EINVAL);
     }
-    0
-}
\ No newline at end of file
+    let mut permission = MapPermission::from_bits((prot as u8) << 1).unwrap() | MapPermission::U;
+    // 页表项中 W 不能单独出现，可写的页也可读
+    if permission.contains(MapPermission::W) {
+        permission |= MapPermission::R;
+    }
+    if mmap_current(start, len, permission) {
+        Ok(0)
+    } else {
+        Err(Errno::EEXIST)
+    }
+}
+
+// [start, start + len) 中的每一页都必须由 sys_mmap 映射，否则返回 EINVAL
+pub fn sys_munmap(start: usize, len: usize) -> SysResult{
+    if !user_range_valid(start, len) || !munmap_current(start, len) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(0)
+}
+
+// 把堆的末尾移动 size 个字节（可以为负），返回原来的末尾，失败返回 ENOMEM
+pub fn sys_sbrk(size: isize) -> SysResult{
+    change_program_brk(size).ok_or(Errno::ENOMEM)
+}
+
+// 父任务返回子任务编号，子任务返回0
+pub fn sys_fork() -> SysResult{
+    fork_current().ok_or(Errno::ENOMEM)
+}
@@ -0,0 +1,118 @@
+// 类似 strace 的系统调用跟踪（trace feature），进入和返回时各输出一行，不返回的系统调用（如 exit）只有第一行：
+// [trace] app_<id> <name> <syscall>(<参数>)
+// [trace] app_<id> <name> <syscall> = <返回值> <<耗时> cycles>
+// 编译时的环境变量 TRACE_APP 为app名时只跟踪该app，未设置或为空时跟踪所有app
+
+use super::errno::SysResult;
+use super::process::MAX_APP_NAME_LEN;
+use super::{
+    SYSCALL_EXIT, SYSCALL_FORK, SYSCALL_GET_TIME, SYSCALL_MMAP, SYSCALL_MUNMAP, SYSCALL_SBRK, SYSCALL_SET_PRIORITY,
+    SYSCALL_SPAWN, SYSCALL_WRITE, SYSCALL_YELD,
+};
+use crate::batch::current_app_name;
+use crate::config::PAGE_SIZE;
+use crate::mm::translated_byte_buffer;
+use crate::task::{current_app, current_user_token};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use riscv::register::cycle;
+
+const TRACE_APP: Option<&str> = option_env!("TRACE_APP");
+
+// 一个正在执行的系统调用：系统调用的名字和开始时的周期数
+pub struct TraceEntry{
+    name: String,
+    start: usize,
+}
+
+fn traced(name: &str) -> bool{
+    match TRACE_APP {
+        Some(app) if !app.is_empty() => app == name,
+        _ => true,
+    }
+}
+
+fn syscall_name(syscall_id: usize) -> String{
+    let name = match syscall_id {
+        SYSCALL_EXIT => "exit",
+        SYSCALL_WRITE => "write",
+        SYSCALL_YELD => "yield",
+        SYSCALL_SET_PRIORITY => "set_priority",
+        SYSCALL_GET_TIME => "get_time",
+        SYSCALL_SBRK => "sbrk",
+        SYSCALL_MUNMAP => "munmap",
+        SYSCALL_FORK => "fork",
+        SYSCALL_MMAP => "mmap",
+        SYSCALL_SPAWN => "spawn",
+        _ => return format!("syscall_{}", syscall_id),
+    };
+    String::from(name)
+}
+
+fn decode_args(syscall_id: usize, args: &[usize; 6]) -> String{
+    match syscall_id {
+        SYSCALL_EXIT => format!("code={}", args[0] as i32),
+        SYSCALL_WRITE => format!("fd={}, buf={:#x}, len={}", args[0], args[1], args[2]),
+        SYSCALL_YELD | SYSCALL_FORK => String::new(),
+        SYSCALL_SET_PRIORITY => format!("prio={}", args[0] as isize),
+        SYSCALL_GET_TIME => format!("ts={:#x}, tz={}", args[0], args[1]),
+        SYSCALL_SBRK => format!("size={}", args[0] as isize),
+        SYSCALL_MUNMAP => format!("start={:#x}, len={:#x}", args[0], args[1]),
+        SYSCALL_MMAP => format!("start={:#x}, len={:#x}, prot={:#x}", args[0], args[1], args[2]),
+        SYSCALL_SPAWN => match peek_user_str(args[0], MAX_APP_NAME_LEN) {
+            Some(name) => format!("name={:?}", name),
+            None => format!("name={:#x}", args[0]),
+        },
+        _ => format!(
+            "{:#x}, {:#x}, {:#x}, {:#x}, {:#x}, {:#x}",
+            args[0], args[1], args[2], args[3], args[4], args[5]
+        ),
+    }
+}
+
+// 只读取已经驻留的页中以'\0'结尾的字符串，不像 read_user_str 那样先处理缺页，
+// 跟踪不能改变缺页次数；字符串所在的页还没有分配、已换出或者字符串过长时返回 None
+fn peek_user_str(ptr: usize, max_len: usize) -> Option<String>{
+    let mut bytes = Vec::new();
+    let mut va = ptr;
+    loop {
+        let len = PAGE_SIZE - va % PAGE_SIZE;
+        let buffers = translated_byte_buffer(current_user_token(), va as *const u8, len, false)?;
+        for &ch in buffers.iter().flat_map(|buffer| buffer.iter()){
+            if ch == 0 {
+                return String::from_utf8(bytes).ok();
+            }
+            if bytes.len() == max_len {
+                return None;
+            }
+            bytes.push(ch);
+        }
+        va += len;
+    }
+}
+
+// 系统调用开始，在调用之前输出它的参数；当前app不被跟踪时返回 None
+pub fn enter(syscall_id: usize, args: &[usize; 6]) -> Option<TraceEntry>{
+    if !traced(current_app_name()) {
+        return None;
+    }
+    let name = syscall_name(syscall_id);
+    println!("[trace] app_{} {} {}({})", current_app(), current_app_name(), name, decode_args(syscall_id, args));
+    Some(TraceEntry{
+        name,
+        start: cycle::read(),
+    })
+}
+
+// 系统调用返回，耗时包括其间切换到其他任务的时间（如 yield）
+pub fn exit(syscall_id: usize, entry: TraceEntry, result: &SysResult){
+    let cycles = cycle::read().wrapping_sub(entry.start);
+    let ret = match result {
+        // sbrk 返回地址
+        Ok(value) if syscall_id == SYSCALL_SBRK => format!("{:#x}", value),
+        Ok(value) => format!("{}", value),
+        Err(errno) => format!("-{} {:?}", *errno as isize, errno),
+    };
+    println!("[trace] app_{} {} {} = {} <{} cycles>", current_app(), current_app_name(), entry.name, ret, cycles);
+}
@@ -0,0 +1,140 @@
+// 系统调用访问当前app用户内存的唯一途径：先按逻辑段检查整个区间都位于用户可访问（写入时还要可写）的内存中，
+// 之后逐页访问，每一页在访问之前才由 populate_current 处理按需分配、换入或写时复制。
+// 区间无效或某一页无法访问时返回 EFAULT，内核不会访问出错
+
+use super::errno::Errno;
+use crate::config::PAGE_SIZE;
+use crate::mm::translated_byte_buffer;
+use crate::task::{current_range_accessible, current_user_token, populate_current};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem::size_of;
+
+// 当前app地址空间中已经检查过的一段内存 [start, start + len)
+pub struct UserBuffer{
+    start: usize,
+    len: usize,
+    write: bool,
+}
+
+impl UserBuffer{
+    pub fn new(ptr: usize, len: usize, write: bool) -> Result<Self, Errno>{
+        // 只检查逻辑段，不会为一个无效的很大的 len 逐页处理缺页
+        if !current_range_accessible(ptr, len, write) {
+            return Err(Errno::EFAULT);
+        }
+        Ok(Self{
+            start: ptr,
+            len,
+            write,
+        })
+    }
+    // 按地址顺序返回每一页中的部分。一页在返回之前才处理缺页，
+    // 所以即使缓冲区超过 USER_RESIDENT_PAGES 页，前面的页被换出也不影响后面的页
+    pub fn pages(&self) -> UserPages{
+        UserPages{
+            va: self.start,
+            end: self.start + self.len,
+            write: self.write,
+        }
+    }
+}
+
+pub struct UserPages{
+    va: usize,
+    end: usize,
+    write: bool,
+}
+
+impl Iterator for UserPages{
+    // 某一页无法分配或换入时返回 EFAULT，之后结束
+    type Item = Result<&'static mut [u8], Errno>;
+    fn next(&mut self) -> Option<Self::Item>{
+        if self.va >= self.end {
+            return None;
+        }
+        let len = (PAGE_SIZE - self.va % PAGE_SIZE).min(self.end - self.va);
+        let page = if populate_current(self.va, self.write) {
+            translated_byte_buffer(current_user_token(), self.va as *const u8, len, self.write)
+                .and_then(|mut buffers| buffers.pop())
+        } else {
+            None
+        };
+        match page {
+            Some(page) => {
+                self.va += len;
+                Some(Ok(page))
+            },
+            None => {
+                self.va = self.end;
+                Some(Err(Errno::EFAULT))
+            },
+        }
+    }
+}
+
+// 把用户地址空间中 [src, src + dst.len()) 读入 dst
+pub fn copy_from_user(dst: &mut [u8], src: usize) -> Result<(), Errno>{
+    let buffer = UserBuffer::new(src, dst.len(), false)?;
+    let mut start = 0;
+    for page in buffer.pages(){
+        let page = page?;
+        dst[start..start + page.len()].copy_from_slice(page);
+        start += page.len();
+    }
+    Ok(())
+}
+
+// 把 src 写入用户地址空间中 [dst, dst + src.len())
+pub fn copy_to_user(dst: usize, src: &[u8]) -> Result<(), Errno>{
+    let buffer = UserBuffer::new(dst, src.len(), true)?;
+    let mut start = 0;
+    for page in buffer.pages(){
+        let page = page?;
+        page.copy_from_slice(&src[start..start + page.len()]);
+        start += page.len();
+    }
+    Ok(())
+}
+
+// 用户地址空间中的一个 T，不要求对齐，可能跨越两个页
+pub struct UserPtr<T>{
+    addr: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> UserPtr<T>{
+    pub fn new(addr: usize) -> Self{
+        Self{
+            addr,
+            _marker: PhantomData,
+        }
+    }
+    pub fn write(&self, value: T) -> Result<(), Errno>{
+        let bytes = unsafe{ core::slice::from_raw_parts(&value as *const T as *const u8, size_of::<T>()) };
+        copy_to_user(self.addr, bytes)
+    }
+}
+
+// 读取以'\0'结尾的字符串，最多 max_len 个字节（不含'\0'）；过长或不是UTF-8时返回 EINVAL
+pub fn read_user_str(ptr: usize, max_len: usize) -> Result<String, Errno>{
+    let mut bytes = Vec::new();
+    let mut chunk = [0u8; 64];
+    let mut va = ptr;
+    loop {
+        // 每次最多读到页尾，字符串之后的页可能没有映射
+        let len = chunk.len().min(PAGE_SIZE - va % PAGE_SIZE);
+        copy_from_user(&mut chunk[..len], va)?;
+        for &ch in chunk[..len].iter(){
+            if ch == 0 {
+                return String::from_utf8(bytes).map_err(|_| Errno::EINVAL);
+            }
+            if bytes.len() == max_len {
+                return Err(Errno::EINVAL);
+            }
+            bytes.push(ch);
+        }
+        va += len;
+    }
+}
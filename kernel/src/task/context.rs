@@ -0,0 +1,30 @@
+// __switch 保存/恢复的任务上下文：ra, sp 以及被调用者保存的 s0~s11
+
+use crate::trap::trap_return;
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct TaskContext{
+    ra: usize,
+    sp: usize,
+    s: [usize;12],
+}
+
+impl TaskContext{
+    pub fn zero_init() -> Self{
+        Self{
+            ra: 0,
+            sp: 0,
+            s: [0;12],
+        }
+    }
+
+    // 第一次被切换到时，__switch 返回到 trap_return，从TRAP_CONTEXT页中的TrapContext进入用户态
+    pub fn goto_trap_return(kstack_ptr: usize) -> Self{
+        Self{
+            ra: trap_return as usize,
+            sp: kstack_ptr,
+            s: [0;12],
+        }
+    }
+}
@@ -0,0 +1,355 @@
+// 多道程序：每个app对应一个任务，通过 __switch 在任务之间切换
+
+mod context;
+mod sched;
+mod switch;
+#[allow(clippy::module_inception)]
+mod task;
+
+use crate::batch::{finish_batch, get_num_app, mark_app_started, new_app_run};
+use crate::mm::{FaultStats, MapPermission, VirtAddr};
+use crate::sync::UPSafeCell;
+use crate::trap::TrapContext;
+use alloc::vec::Vec;
+use lazy_static::*;
+use sched::{ActiveScheduler, Scheduler};
+use switch::__switch;
+use task::{map_kernel_stack, TaskControlBlock, TaskStatus};
+
+pub use context::TaskContext;
+
+pub struct TaskManager{
+    inner: UPSafeCell<TaskManagerInner>,
+}
+
+struct TaskManagerInner{
+    tasks: Vec<TaskControlBlock>,
+    current_task: usize,
+    scheduler: ActiveScheduler,
+}
+
+lazy_static!{
+    pub static ref TASK_MANAGER: TaskManager = {
+        let num_app = get_num_app();
+        let mut tasks = Vec::new();
+        let mut scheduler = ActiveScheduler::new();
+        for i in 0..num_app{
+            let task = TaskControlBlock::new(i);
+            if task.task_status == TaskStatus::Ready {
+                scheduler.add_task(i);
+            }
+            tasks.push(task);
+        }
+        TaskManager{
+            inner: unsafe{
+                UPSafeCell::new(TaskManagerInner{
+                    tasks,
+                    current_task: 0,
+                    scheduler,
+                })
+            },
+        }
+    };
+}
+
+impl TaskManager{
+    fn run_first_task(&self) -> !{
+        let mut inner = self.inner.exclusive_access();
+        println!("[kernel] scheduler: {}", inner.scheduler.name());
+        let first = match inner.scheduler.fetch_task() {
+            Some(first) => first,
+            None => {
+                // 没有可以运行的app（都无法加载）
+                drop(inner);
+                finish_batch();
+            },
+        };
+        inner.current_task = first;
+        let task0 = &mut inner.tasks[first];
+        task0.switch_in();
+        let next_task_cx_ptr = &task0.task_cx as *const TaskContext;
+        drop(inner);
+        mark_app_started(first);
+        let mut _unused = TaskContext::zero_init();
+        // before this, we should drop local variables that must be dropped manually
+        unsafe{
+            __switch(&mut _unused as *mut TaskContext, next_task_cx_ptr);
+        }
+        panic!("Unreachable in task::run_first_task!");
+    }
+
+    fn mark_current_suspended(&self){
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].switch_out(TaskStatus::Ready);
+        inner.scheduler.add_task(current);
+    }
+
+    fn mark_current_exited(&self){
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].switch_out(TaskStatus::Exited);
+        // 回收用户内存，页表本身在该app再次被spawn时才回收
+        inner.tasks[current].memory_set.recycle_data_pages();
+    }
+
+    // 由调度器决定下一个运行的任务
+    fn find_next_task(&self) -> Option<usize>{
+        self.inner.exclusive_access().scheduler.fetch_task()
+    }
+
+    fn set_current_priority(&self, priority: usize){
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.scheduler.set_priority(current, priority);
+    }
+
+    // 每个app只有一个编号等于app_id的任务，因此只有已经结束的app可以再次运行，
+    // 正在运行或就绪的app不能同时有第二个实例
+    fn spawn_task(&self, app_id: usize) -> bool{
+        let mut inner = self.inner.exclusive_access();
+        if inner.tasks[app_id].task_status != TaskStatus::Exited {
+            return false;
+        }
+        new_app_run(app_id);
+        if !inner.tasks[app_id].reload(app_id) {
+            return false;
+        }
+        inner.scheduler.add_new_task(app_id, None);
+        true
+    }
+
+    fn on_tick(&self) -> bool{
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.scheduler.on_tick(current)
+    }
+
+    fn get_current_cpu_time_us(&self) -> usize{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].cpu_time_us()
+    }
+
+    fn get_current_fault_stats(&self) -> FaultStats{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].memory_set.fault_stats()
+    }
+
+    fn get_current_app(&self) -> usize{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].app_id
+    }
+
+    fn current_is_forked(&self) -> bool{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].parent.is_some()
+    }
+
+    // 复用已结束的fork出的任务的编号和内核栈，没有时新建一个任务，返回子任务编号；
+    // 没有足够的空闲页帧时返回 None
+    fn fork_current(&self) -> Option<usize>{
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let reusable = (0..inner.tasks.len()).find(|id| {
+            inner.tasks[*id].parent.is_some() && inner.tasks[*id].task_status == TaskStatus::Exited
+        });
+        let child_id = reusable.unwrap_or(inner.tasks.len());
+        let child = inner.tasks[current].fork(current, child_id)?;
+        if child_id == inner.tasks.len() {
+            map_kernel_stack(child_id)?;
+            inner.tasks.push(child);
+        } else {
+            inner.tasks[child_id] = child;
+        }
+        inner.scheduler.add_new_task(child_id, Some(current));
+        Some(child_id)
+    }
+
+    fn handle_page_fault_current(&self, va: usize, write: bool) -> bool{
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].memory_set.handle_page_fault(VirtAddr::from(va), write)
+    }
+
+    fn current_range_accessible(&self, start: usize, len: usize, write: bool) -> bool{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].memory_set.user_range_accessible(start, len, write)
+    }
+
+    fn populate_current(&self, va: usize, write: bool) -> bool{
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].memory_set.populate_page(va, write)
+    }
+
+    fn current_stack_overflow(&self, va: usize) -> bool{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].in_stack_guard(va)
+    }
+
+    fn get_current_token(&self) -> usize{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].get_user_token()
+    }
+
+    fn get_current_trap_cx(&self) -> &'static mut TrapContext{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].get_trap_cx()
+    }
+
+    fn mmap_current(&self, start: usize, len: usize, permission: MapPermission) -> bool{
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].memory_set.mmap(VirtAddr::from(start), len, permission)
+    }
+
+    fn mmap_reserved(&self, start: usize, len: usize) -> bool{
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].overlaps_reserved(start, start + len)
+    }
+
+    fn munmap_current(&self, start: usize, len: usize) -> bool{
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].memory_set.munmap(VirtAddr::from(start), len)
+    }
+
+    fn change_current_program_brk(&self, size: isize) -> Option<usize>{
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].change_program_brk(size)
+    }
+
+    fn run_next_task(&self){
+        if let Some(next) = self.find_next_task(){
+            let mut inner = self.inner.exclusive_access();
+            let current = inner.current_task;
+            inner.tasks[next].switch_in();
+            inner.current_task = next;
+            let current_task_cx_ptr = &mut inner.tasks[current].task_cx as *mut TaskContext;
+            let next_task_cx_ptr = &inner.tasks[next].task_cx as *const TaskContext;
+            let next_is_forked = inner.tasks[next].parent.is_some();
+            drop(inner);
+            if !next_is_forked {
+                mark_app_started(next);
+            }
+            // before this, we should drop local variables that must be dropped manually
+            unsafe{
+                __switch(current_task_cx_ptr, next_task_cx_ptr);
+            }
+            // go back to user mode
+        }else{
+            println!("[kernel] All applications completed!");
+            finish_batch();
+        }
+    }
+}
+
+
+//interfaces
+
+pub fn run_first_task() -> !{
+    TASK_MANAGER.run_first_task()
+}
+
+// 当前正在运行的任务所属的app的编号
+pub fn current_app() -> usize{
+    TASK_MANAGER.get_current_app()
+}
+
+// 当前任务是否是fork出的任务
+pub fn current_is_forked() -> bool{
+    TASK_MANAGER.current_is_forked()
+}
+
+// sys_fork: 复制当前任务，返回子任务编号，没有足够的空闲页帧时返回 None
+pub fn fork_current() -> Option<usize>{
+    TASK_MANAGER.fork_current()
+}
+
+// 当前任务访问 va 时缺页：按需分配、从交换区换入或写时复制，无法处理时返回false
+pub fn handle_page_fault_current(va: usize, write: bool) -> bool{
+    TASK_MANAGER.handle_page_fault_current(va, write)
+}
+
+// [start, start + len) 是否整个位于当前任务用户可读（write 为true时可写）的逻辑段中
+pub fn current_range_accessible(start: usize, len: usize, write: bool) -> bool{
+    TASK_MANAGER.current_range_accessible(start, len, write)
+}
+
+// 内核直接访问当前任务 va 所在的页之前调用，返回该页是否可以访问
+pub fn populate_current(va: usize, write: bool) -> bool{
+    TASK_MANAGER.populate_current(va, write)
+}
+
+// 缺页的地址 va 是否位于当前任务的用户栈之下的guard page中
+pub fn current_stack_overflow(va: usize) -> bool{
+    TASK_MANAGER.current_stack_overflow(va)
+}
+
+// 当前app地址空间的satp
+pub fn current_user_token() -> usize{
+    TASK_MANAGER.get_current_token()
+}
+
+// 当前app的TrapContext
+pub fn current_trap_cx() -> &'static mut TrapContext{
+    TASK_MANAGER.get_current_trap_cx()
+}
+
+// 当前app已使用的CPU时间
+pub fn current_cpu_time_us() -> usize{
+    TASK_MANAGER.get_current_cpu_time_us()
+}
+
+// 当前任务的缺页次数，在任务结束时计入app的记录
+pub fn current_fault_stats() -> FaultStats{
+    TASK_MANAGER.get_current_fault_stats()
+}
+
+// sys_set_priority: 修改当前app的优先级
+pub fn set_current_priority(priority: usize){
+    TASK_MANAGER.set_current_priority(priority);
+}
+
+// sys_spawn: 重新运行一个已结束的app，app仍在运行时返回false
+pub fn spawn_task(app_id: usize) -> bool{
+    TASK_MANAGER.spawn_task(app_id)
+}
+
+// sys_mmap: 在当前app的地址空间中映射匿名内存
+pub fn mmap_current(start: usize, len: usize, permission: MapPermission) -> bool{
+    TASK_MANAGER.mmap_current(start, len, permission)
+}
+
+// sys_mmap: [start, start + len) 是否与当前app不能被mmap的区域重叠
+pub fn mmap_reserved(start: usize, len: usize) -> bool{
+    TASK_MANAGER.mmap_reserved(start, len)
+}
+
+// sys_munmap: 取消当前app地址空间中的映射
+pub fn munmap_current(start: usize, len: usize) -> bool{
+    TASK_MANAGER.munmap_current(start, len)
+}
+
+// sys_sbrk: 移动当前app的堆的末尾
+pub fn change_program_brk(size: isize) -> Option<usize>{
+    TASK_MANAGER.change_current_program_brk(size)
+}
+
+// 时钟中断：由调度策略决定是否抢占当前app
+pub fn on_tick() -> bool{
+    TASK_MANAGER.on_tick()
+}
+
+// sys_yield: 让出CPU，切换到下一个就绪的app
+pub fn suspend_current_and_run_next(){
+    TASK_MANAGER.mark_current_suspended();
+    TASK_MANAGER.run_next_task();
+}
+
+// app退出或被kill，切换到下一个就绪的app
+pub fn exit_current_and_run_next(){
+    TASK_MANAGER.mark_current_exited();
+    TASK_MANAGER.run_next_task();
+}
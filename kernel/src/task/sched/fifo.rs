@@ -0,0 +1,29 @@
+use super::{Scheduler, TaskQueue};
+
+// 先来先服务，不抢占：只有app主动yield或退出时才切换
+pub struct FifoScheduler{
+    ready: TaskQueue,
+}
+
+impl Scheduler for FifoScheduler{
+    fn new() -> Self{
+        Self{
+            ready: TaskQueue::new(),
+        }
+    }
+    fn name(&self) -> &'static str{
+        "fifo"
+    }
+    fn add_task(&mut self, id: usize){
+        self.ready.push_back(id);
+    }
+    fn fetch_task(&mut self) -> Option<usize>{
+        self.ready.pop_front()
+    }
+    fn on_tick(&mut self, _current: usize) -> bool{
+        false
+    }
+    fn remove_task(&mut self, id: usize){
+        self.ready.remove(id);
+    }
+}
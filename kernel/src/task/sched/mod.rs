@@ -0,0 +1,72 @@
+// 可替换的调度策略，通过cargo feature选择：sched_fifo / sched_rr / sched_stride
+
+mod fifo;
+mod rr;
+mod stride;
+
+use alloc::collections::VecDeque;
+
+pub use fifo::FifoScheduler;
+pub use rr::RRScheduler;
+pub use stride::StrideScheduler;
+
+#[cfg(any(
+    all(feature = "sched_fifo", feature = "sched_rr"),
+    all(feature = "sched_fifo", feature = "sched_stride"),
+    all(feature = "sched_rr", feature = "sched_stride"),
+))]
+compile_error!("select at most one of the sched_fifo / sched_rr / sched_stride features");
+
+#[cfg(feature = "sched_fifo")]
+pub type ActiveScheduler = FifoScheduler;
+#[cfg(feature = "sched_stride")]
+pub type ActiveScheduler = StrideScheduler;
+// round-robin when no policy is selected
+#[cfg(not(any(feature = "sched_fifo", feature = "sched_stride")))]
+pub type ActiveScheduler = RRScheduler;
+
+// 调度器只管理Ready任务的编号，任务的状态和上下文仍由TaskManager维护
+pub trait Scheduler{
+    fn new() -> Self where Self: Sized;
+    fn name(&self) -> &'static str;
+    // 任务变为Ready，加入就绪队列
+    fn add_task(&mut self, id: usize);
+    // 新建的任务（spawn 或 fork）变为Ready，编号可能是复用的，之前的调度信息不再有效；
+    // parent 为fork时的父任务
+    fn add_new_task(&mut self, id: usize, _parent: Option<usize>){
+        self.add_task(id);
+    }
+    // 取出下一个要运行的任务
+    fn fetch_task(&mut self) -> Option<usize>;
+    // 时钟中断时调用，返回是否抢占当前正在运行的任务
+    fn on_tick(&mut self, current: usize) -> bool;
+    // 把任务从就绪队列中移除（如被kill）
+    fn remove_task(&mut self, id: usize);
+    // 只有基于优先级的调度器关心 sys_set_priority
+    fn set_priority(&mut self, _id: usize, _priority: usize){}
+}
+
+// 就绪队列，用于FIFO和RR
+pub struct TaskQueue{
+    queue: VecDeque<usize>,
+}
+
+impl TaskQueue{
+    pub const fn new() -> Self{
+        Self{
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn push_back(&mut self, id: usize){
+        self.queue.push_back(id);
+    }
+
+    pub fn pop_front(&mut self) -> Option<usize>{
+        self.queue.pop_front()
+    }
+
+    pub fn remove(&mut self, id: usize){
+        self.queue.retain(|x| *x != id);
+    }
+}
@@ -0,0 +1,29 @@
+use super::{Scheduler, TaskQueue};
+
+// 时间片轮转，每个时钟中断都抢占当前任务并放到队尾
+pub struct RRScheduler{
+    ready: TaskQueue,
+}
+
+impl Scheduler for RRScheduler{
+    fn new() -> Self{
+        Self{
+            ready: TaskQueue::new(),
+        }
+    }
+    fn name(&self) -> &'static str{
+        "round-robin"
+    }
+    fn add_task(&mut self, id: usize){
+        self.ready.push_back(id);
+    }
+    fn fetch_task(&mut self) -> Option<usize>{
+        self.ready.pop_front()
+    }
+    fn on_tick(&mut self, _current: usize) -> bool{
+        true
+    }
+    fn remove_task(&mut self, id: usize){
+        self.ready.remove(id);
+    }
+}
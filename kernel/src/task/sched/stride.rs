@@ -0,0 +1,81 @@
+use super::Scheduler;
+use crate::config::{BIG_STRIDE, DEFAULT_PRIORITY};
+use alloc::vec::Vec;
+
+// stride 调度：选择pass最小的任务，每次被调度 pass += BIG_STRIDE / priority
+// 以任务编号为下标，第一次见到某个任务时扩展
+pub struct StrideScheduler{
+    ready: Vec<bool>,
+    priority: Vec<usize>,
+    pass: Vec<usize>,
+    // 最近一次被调度的任务，即正在运行的任务
+    running: Option<usize>,
+}
+
+impl StrideScheduler{
+    fn ensure_task(&mut self, id: usize){
+        if id >= self.ready.len() {
+            self.ready.resize(id + 1, false);
+            self.priority.resize(id + 1, DEFAULT_PRIORITY);
+            self.pass.resize(id + 1, 0);
+        }
+    }
+
+    // ids 中pass最小的任务
+    fn min_pass_task(&self, ids: impl Iterator<Item = usize>) -> Option<usize>{
+        ids.reduce(|min, id| if self.pass_less(id, min) { id } else { min })
+    }
+
+    // priority >= 2 保证任意两个pass之差不超过 BIG_STRIDE / 2，溢出后按有符号差比较仍然正确
+    fn pass_less(&self, a: usize, b: usize) -> bool{
+        (self.pass[a].wrapping_sub(self.pass[b]) as isize) < 0
+    }
+}
+
+impl Scheduler for StrideScheduler{
+    fn new() -> Self{
+        Self{
+            ready: Vec::new(),
+            priority: Vec::new(),
+            pass: Vec::new(),
+            running: None,
+        }
+    }
+    fn name(&self) -> &'static str{
+        "stride"
+    }
+    fn add_task(&mut self, id: usize){
+        self.ensure_task(id);
+        self.ready[id] = true;
+    }
+    // 新任务的pass从父任务的pass开始，否则从就绪任务和正在运行的任务中最小的pass开始，
+    // 从0或复用编号时残留的pass开始会破坏上面pass之差的保证
+    fn add_new_task(&mut self, id: usize, parent: Option<usize>){
+        self.ensure_task(id);
+        let start = parent.or_else(|| {
+            self.min_pass_task((0..self.ready.len()).filter(|i| self.ready[*i]).chain(self.running))
+        });
+        self.pass[id] = start.map_or(0, |i| self.pass[i]);
+        self.priority[id] = DEFAULT_PRIORITY;
+        self.ready[id] = true;
+    }
+    fn fetch_task(&mut self) -> Option<usize>{
+        let next = self.min_pass_task((0..self.ready.len()).filter(|id| self.ready[*id]))?;
+        self.ready[next] = false;
+        self.running = Some(next);
+        self.pass[next] = self.pass[next].wrapping_add(BIG_STRIDE / self.priority[next]);
+        Some(next)
+    }
+    fn on_tick(&mut self, _current: usize) -> bool{
+        true
+    }
+    fn remove_task(&mut self, id: usize){
+        if id < self.ready.len() {
+            self.ready[id] = false;
+        }
+    }
+    fn set_priority(&mut self, id: usize, priority: usize){
+        self.ensure_task(id);
+        self.priority[id] = priority;
+    }
+}
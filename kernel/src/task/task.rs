@@ -0,0 +1,189 @@
+use super::TaskContext;
+use crate::batch::load_app;
+use crate::config::{kernel_stack_position, PAGE_SIZE, TRAP_CONTEXT, USER_HEAP_SIZE};
+use crate::mm::{MapPermission, MemorySet, PhysPageNum, UserLayout, VirtAddr, KERNEL_SPACE};
+use crate::timer::get_time_us;
+use crate::trap::TrapContext;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum TaskStatus{
+    Ready,
+    Running,
+    Exited,
+}
+
+// 任务控制块，调度相关的信息（如优先级）由调度器维护
+// 每个app有一个编号等于app_id的任务，fork出的任务编号从 num_app 开始
+pub struct TaskControlBlock{
+    // 任务所属的app，fork出的任务与父任务相同
+    pub app_id: usize,
+    // fork出的任务的父任务编号
+    pub parent: Option<usize>,
+    pub task_status: TaskStatus,
+    pub task_cx: TaskContext,
+    // 已使用的CPU时间，不包括本次运行；switch_in_us 为本次开始运行的时间
+    pub cpu_time_us: usize,
+    pub switch_in_us: usize,
+    // app的地址空间，以及其中存放TrapContext的物理页
+    pub memory_set: MemorySet,
+    pub trap_cx_ppn: PhysPageNum,
+    // 用户栈之下的guard page为 [user_stack_bottom - PAGE_SIZE, user_stack_bottom)
+    pub user_stack_bottom: usize,
+    // 堆为 [heap_bottom, program_brk)
+    pub heap_bottom: usize,
+    pub program_brk: usize,
+}
+
+// 在内核地址空间中映射编号为 task_id 的任务的内核栈，返回栈顶；没有足够的空闲页帧时返回 None
+pub fn map_kernel_stack(task_id: usize) -> Option<usize>{
+    let (kernel_stack_bottom, kernel_stack_top) = kernel_stack_position(task_id);
+    let mapped = KERNEL_SPACE.exclusive_access().insert_framed_area(
+        kernel_stack_bottom.into(),
+        kernel_stack_top.into(),
+        MapPermission::R | MapPermission::W,
+    );
+    mapped.then_some(kernel_stack_top)
+}
+
+impl TaskControlBlock{
+    // 为app建立地址空间并在内核地址空间中映射它的内核栈，第一次被调度时从 trap_return 进入用户态。
+    // app无法加载时任务处于结束状态，不会被调度。只在启动时调用，此时没有空闲页帧无法继续
+    pub fn new(app_id: usize) -> Self{
+        let kernel_stack_top = map_kernel_stack(app_id).expect("no free frame for the kernel stack");
+        let mut task = Self{
+            app_id,
+            parent: None,
+            task_status: TaskStatus::Exited,
+            task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+            cpu_time_us: 0,
+            switch_in_us: 0,
+            memory_set: MemorySet::new_bare().expect("no free frame for the page table"),
+            trap_cx_ppn: PhysPageNum(0),
+            user_stack_bottom: 0,
+            heap_bottom: 0,
+            program_brk: 0,
+        };
+        task.reload(app_id);
+        task
+    }
+
+    // 重新加载已结束的app（sys_spawn），内核栈保持不变，旧的地址空间在这里被回收；
+    // app无法加载时返回false，任务保持结束状态
+    pub fn reload(&mut self, app_id: usize) -> bool{
+        let (memory_set, trap_cx_ppn, layout) = match Self::load(app_id) {
+            Some(loaded) => loaded,
+            None => return false,
+        };
+        self.memory_set = memory_set;
+        self.trap_cx_ppn = trap_cx_ppn;
+        self.user_stack_bottom = layout.user_stack_bottom;
+        self.heap_bottom = layout.heap_bottom;
+        self.program_brk = layout.heap_bottom;
+        self.task_status = TaskStatus::Ready;
+        self.task_cx = TaskContext::goto_trap_return(kernel_stack_position(app_id).1);
+        self.cpu_time_us = 0;
+        true
+    }
+
+    // fork：子任务的编号为 task_id，它的内核栈在被调度之前必须已经映射。子任务从同一个系统调用返回，返回值为0；
+    // 没有足够的空闲页帧复制地址空间时返回 None
+    pub fn fork(&mut self, parent_id: usize, task_id: usize) -> Option<Self>{
+        let memory_set = self.memory_set.fork()?;
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+        let kernel_stack_top = kernel_stack_position(task_id).1;
+        let trap_cx: &mut TrapContext = trap_cx_ppn.get_mut();
+        trap_cx.kernel_sp = kernel_stack_top;
+        trap_cx.reg[10] = 0;
+        Some(Self{
+            app_id: self.app_id,
+            parent: Some(parent_id),
+            task_status: TaskStatus::Ready,
+            task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+            cpu_time_us: 0,
+            switch_in_us: 0,
+            memory_set,
+            trap_cx_ppn,
+            user_stack_bottom: self.user_stack_bottom,
+            heap_bottom: self.heap_bottom,
+            program_brk: self.program_brk,
+        })
+    }
+
+    fn load(app_id: usize) -> Option<(MemorySet, PhysPageNum, UserLayout)>{
+        let (memory_set, trap_cx, layout) = load_app(app_id)?;
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+        *trap_cx_ppn.get_mut() = trap_cx;
+        Some((memory_set, trap_cx_ppn, layout))
+    }
+
+    // addr 是否位于用户栈之下的guard page中
+    pub fn in_stack_guard(&self, addr: usize) -> bool{
+        addr < self.user_stack_bottom && addr >= self.user_stack_bottom - PAGE_SIZE
+    }
+
+    // [start, end) 是否与不能被mmap的区域重叠：第0页、用户栈之下的guard page，
+    // 以及从用户栈之上的guard page到堆可以增长到的最高地址
+    pub fn overlaps_reserved(&self, start: usize, end: usize) -> bool{
+        let user_stack_top = self.heap_bottom - PAGE_SIZE;
+        let reserved = [
+            (0, PAGE_SIZE),
+            (self.user_stack_bottom - PAGE_SIZE, self.user_stack_bottom),
+            (user_stack_top, self.heap_bottom + USER_HEAP_SIZE),
+        ];
+        reserved.iter().any(|&(l, r)| start < r && l < end)
+    }
+
+    pub fn get_trap_cx(&self) -> &'static mut TrapContext{
+        self.trap_cx_ppn.get_mut()
+    }
+
+    pub fn get_user_token(&self) -> usize{
+        self.memory_set.token()
+    }
+
+    // 把堆的末尾移动 size 个字节，成功时返回原来的末尾；堆不能缩小到 heap_bottom 以下，
+    // 也不能超过 USER_HEAP_SIZE 或扩展到其他逻辑段
+    pub fn change_program_brk(&mut self, size: isize) -> Option<usize>{
+        let old_brk = self.program_brk;
+        let new_brk = old_brk.checked_add_signed(size)?;
+        if new_brk < self.heap_bottom || new_brk > self.heap_bottom + USER_HEAP_SIZE {
+            return None;
+        }
+        let ok = if size < 0 {
+            self.memory_set.shrink_to(VirtAddr::from(self.heap_bottom), VirtAddr::from(new_brk))
+        } else {
+            self.memory_set.append_to(VirtAddr::from(self.heap_bottom), VirtAddr::from(new_brk))
+        };
+        if ok {
+            self.program_brk = new_brk;
+            Some(old_brk)
+        } else {
+            None
+        }
+    }
+
+    pub fn switch_in(&mut self){
+        self.task_status = TaskStatus::Running;
+        self.switch_in_us = get_time_us();
+    }
+
+    pub fn switch_out(&mut self, status: TaskStatus){
+        self.task_status = status;
+        self.cpu_time_us += get_time_us() - self.switch_in_us;
+    }
+
+    // 包括本次运行在内的总CPU时间
+    pub fn cpu_time_us(&self) -> usize{
+        if self.task_status == TaskStatus::Running {
+            self.cpu_time_us + get_time_us() - self.switch_in_us
+        } else {
+            self.cpu_time_us
+        }
+    }
+}
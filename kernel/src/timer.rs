@@ -15,6 +15,7 @@ pub fn get_time_us() -> usize{
     ts / (CLOCK_FREQ/MICRO_PER_SEC)
 }
 
+// trigger a supervisor timer interrupt after one time slice (1/TICKS_PER_SEC s)
 pub fn set_next_trigger(){
     set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
 }
\ No newline at end of file
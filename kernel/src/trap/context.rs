@@ -5,6 +5,10 @@ pub struct TrapContext{
     pub reg: [usize;32],
     pub sstatus: Sstatus,
     pub sepc: usize,
+    // 以下三项在app运行期间不变，__alltraps 用它们切换到内核地址空间和该app的内核栈并跳转到trap_handler
+    pub kernel_satp: usize,
+    pub kernel_sp: usize,
+    pub trap_handler: usize,
 }
 
 impl TrapContext{
@@ -15,13 +19,22 @@ impl TrapContext{
     // 一些便捷的操作上下文的函数...
     // ...
 
-    pub fn app_init_context(addr: usize, sp: usize) -> Self{
+    pub fn app_init_context(
+        addr: usize,
+        sp: usize,
+        kernel_satp: usize,
+        kernel_sp: usize,
+        trap_handler: usize,
+    ) -> Self{
         let mut sstatus = sstatus::read(); // CSR sstatus
         sstatus.set_spp(SPP::User); //previous privilege mode: user mode
         let mut cx = Self {
             reg: [0; 32],
             sstatus,
             sepc: addr, // entry point of app
+            kernel_satp,
+            kernel_sp,
+            trap_handler,
         };
         cx.set_sp(sp); // app's user stack pointer
         cx // return initial Trap Context of app
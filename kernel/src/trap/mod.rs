@@ -1,42 +1,98 @@
 mod context;
 pub use context::TrapContext;
 
-use core::arch::global_asm;
+use core::arch::{asm, global_asm};
 global_asm!(include_str!("trap.S"));
 use riscv::register::{
     mtvec::TrapMode,
-    scause::{self, Exception, Trap},
-    stval, stvec,
+    scause::{self, Exception, Interrupt, Trap},
+    sie, stval, stvec,
 };
 
-use crate::{batch::run_next_app, syscall::syscall};
+use crate::{batch::{current_time_limit_exceeded, kill_current_app}, syscall::syscall};
+use crate::config::{TRAMPOLINE, TRAP_CONTEXT};
+use crate::task::{
+    current_stack_overflow, current_trap_cx, current_user_token, handle_page_fault_current, on_tick,
+    suspend_current_and_run_next,
+};
+use crate::timer::set_next_trigger;
 
-// set trap settings
+// set trap settings, the kernel runs with __kernel_trap until trap_return enters U mode
 pub fn init(){
+    set_kernel_trap_entry();
+}
+
+// traps taken in S mode are kernel bugs
+fn set_kernel_trap_entry(){
     extern "C" {
-        fn __alltraps();
+        fn __kernel_trap();
+    }
+    unsafe {
+        stvec::write(__kernel_trap as usize, TrapMode::Direct);
+    }
+}
+
+// traps from U mode enter __alltraps through the trampoline, which is mapped in every address space
+fn set_user_trap_entry(){
+    unsafe {
+        stvec::write(TRAMPOLINE, TrapMode::Direct);
     }
+}
+
+// enable supervisor timer interrupt, used for preemptive scheduling
+pub fn enable_timer_interrupt(){
     unsafe {
-        stvec::write(__alltraps as usize, TrapMode::Direct);
+        sie::set_stimer();
     }
 }
-// trap or syscall
+
+// trap or syscall, entered from __alltraps in kernel space, the TrapContext is in the app's TRAP_CONTEXT page
 #[no_mangle]
-pub fn trap_handler(cx: &mut TrapContext)  -> &mut TrapContext{
+pub fn trap_handler() -> !{
+    set_kernel_trap_entry();
+    let cx = current_trap_cx();
     let scause = scause::read(); // get trap cause
     let stval = stval::read(); // get extra value
+    let page_fault = matches!(
+        scause.cause(),
+        Trap::Exception(Exception::LoadPageFault | Exception::StorePageFault | Exception::InstructionPageFault)
+    );
     match scause.cause(){
         Trap::Exception(Exception::UserEnvCall) => {
             cx.sepc += 4;
-            cx.reg[10] = syscall(cx.reg[17], [cx.reg[10], cx.reg[11], cx.reg[12]]) as usize;
+            // a7 is the syscall id, a0-a5 are the arguments
+            let args = [cx.reg[10], cx.reg[11], cx.reg[12], cx.reg[13], cx.reg[14], cx.reg[15]];
+            cx.reg[10] = syscall(cx.reg[17], args) as usize;
+        },
+        Trap::Exception(e) if page_fault && handle_page_fault_current(stval, e == Exception::StorePageFault) => {
+            // the page was allocated on demand, swapped in or copied after fork, retry the access
+        },
+        Trap::Exception(_) if page_fault && current_stack_overflow(stval) => {
+            println!(
+                "[kernel] Stack overflow in application, stval = {:#x}, sepc = {:#x}, kernel killed it.",
+                stval, cx.sepc
+            );
+            kill_current_app("StackOverflow", stval, cx.sepc);
         },
-        Trap::Exception(Exception::StoreFault) | Trap::Exception(Exception::StorePageFault) =>{
-            println!("[kernel] PageFault in application, kernel killed it.");
-            run_next_app();
+        Trap::Exception(_) => {
+            // any other exception from U mode only kills the faulting app
+            let reason = exception_name(scause.code());
+            println!(
+                "[kernel] {} in application, stval = {:#x}, sepc = {:#x}, kernel killed it.",
+                reason, stval, cx.sepc
+            );
+            kill_current_app(reason, stval, cx.sepc);
         },
-        Trap::Exception(Exception::IllegalInstruction) => {
-            println!("[kernel] IllegalInstruction in application, kernel killed it.");
-            run_next_app();
+        Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            // time slice used up, let the scheduler decide whether to preempt current app
+            set_next_trigger();
+            if current_time_limit_exceeded() {
+                println!("[kernel] Time limit exceeded in application, kernel killed it.");
+                kill_current_app("TimeLimitExceeded", 0, cx.sepc);
+            }
+            if on_tick() {
+                suspend_current_and_run_next();
+            }
         },
         _=>{
             panic!(
@@ -46,5 +102,66 @@ pub fn trap_handler(cx: &mut TrapContext)  -> &mut TrapContext{
             );
         }
     }
-    cx
-}
\ No newline at end of file
+    trap_return();
+}
+
+// return to U mode of the current app: switch to its address space in __restore and sret
+#[no_mangle]
+pub fn trap_return() -> !{
+    set_user_trap_entry();
+    let trap_cx_ptr = TRAP_CONTEXT;
+    let user_satp = current_user_token();
+    extern "C" {
+        fn __alltraps();
+        fn __restore();
+    }
+    // __restore in the trampoline page
+    let restore_va = __restore as usize - __alltraps as usize + TRAMPOLINE;
+    unsafe{
+        asm!(
+            "fence.i",
+            "jr {restore_va}",
+            restore_va = in(reg) restore_va,
+            in("a0") trap_cx_ptr,
+            in("a1") user_satp,
+            options(noreturn)
+        );
+    }
+}
+
+// name of an exception code, riscv::Exception lacks some of them (e.g. LoadMisaligned)
+fn exception_name(code: usize) -> &'static str{
+    match code {
+        0 => "InstructionMisaligned",
+        1 => "InstructionFault",
+        2 => "IllegalInstruction",
+        3 => "Breakpoint",
+        4 => "LoadMisaligned",
+        5 => "LoadFault",
+        6 => "StoreMisaligned",
+        7 => "StoreFault",
+        8 => "UserEnvCall",
+        12 => "InstructionPageFault",
+        13 => "LoadPageFault",
+        15 => "StorePageFault",
+        _ => "UnknownException",
+    }
+}
+
+// trap taken in S mode, called by __kernel_trap with all registers saved in cx
+#[no_mangle]
+pub fn trap_from_kernel(cx: &TrapContext) -> !{
+    let scause = scause::read();
+    let stval = stval::read();
+    println!(
+        "[kernel] trap from kernel: {:?}, scause = {:#x}, stval = {:#x}, sepc = {:#x}, {:?}",
+        scause.cause(), scause.bits(), stval, cx.sepc, cx.sstatus
+    );
+    for i in (0..32).step_by(4) {
+        println!(
+            "[kernel] x{:<2} = {:#018x}  x{:<2} = {:#018x}  x{:<2} = {:#018x}  x{:<2} = {:#018x}",
+            i, cx.reg[i], i + 1, cx.reg[i + 1], i + 2, cx.reg[i + 2], i + 3, cx.reg[i + 3]
+        );
+    }
+    panic!("Unsupported trap {:?} from kernel, stval = {:#x}!", scause.cause(), stval);
+}